@@ -1,15 +1,19 @@
+use crate::api::oauth::refresh_access_token;
 use crate::common::error::AppError;
 use crate::common::fileutils::{create_temp_dir, TEMP_DIR_PATH};
+use crate::common::http_client::create_http_client;
 use crate::models::config::ImageFormat;
-use crate::models::figma::Document;
+use crate::models::credentials::{resolve_token, Credentials, FigmaToken, OAuthCredentials};
+use crate::models::figma::{Document, Frame};
 use reqwest::{
-    blocking::{Client, Response},
-    Error, StatusCode,
+    blocking::{Client, RequestBuilder, Response},
+    StatusCode,
 };
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::fs::{self, File};
+use std::fs::File;
 use std::io::BufReader;
 
 /// Response from Figma API.
@@ -18,8 +22,52 @@ use std::io::BufReader;
 ///
 /// Details: https://www.figma.com/developers/api#get-files-endpoint
 #[derive(Debug, Deserialize, Serialize)]
-struct FigmaGetFileResponse {
-    document: Document,
+pub struct FigmaGetFileResponse {
+    pub document: Document,
+    /// Version of the file. Documents cached by older versions of the app have no version
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+/// User of Figma API, the owner of the token.
+///
+/// Endpoint: `https://api.figma.com/v1/me`
+///
+/// Details: https://www.figma.com/developers/api#users-endpoints
+#[derive(Debug, Deserialize)]
+pub struct FigmaUser {
+    pub handle: String,
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+/// Response from Figma API for the file requested with `depth=1`,
+/// so the document is not loaded deeper than pages.
+///
+/// Endpoint: `https://api.figma.com/v1/files/:file_key`
+///
+/// Details: https://www.figma.com/developers/api#get-files-endpoint
+#[derive(Debug, Deserialize)]
+struct FigmaGetFileVersionResponse {
+    version: String,
+}
+
+/// Response from Figma API.
+///
+/// Endpoint: `https://api.figma.com/v1/files/:file_key/nodes`
+///
+/// Details: https://www.figma.com/developers/api#get-file-nodes-endpoint
+#[derive(Debug, Deserialize)]
+struct FigmaGetFileNodesResponse {
+    #[serde(default)]
+    version: Option<String>,
+    /// Nodes by ids. Nodes which don't exist are `null`
+    nodes: HashMap<String, Option<FigmaFileNode>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FigmaFileNode {
+    document: Frame,
 }
 
 /// Response from Figma API.
@@ -39,12 +87,14 @@ struct FigmaGetImageResponse {
 /// # Example
 ///
 /// ```rust
-/// let api = FigmaApi::new(create_http_client(&figma_personal_access_token));
+/// let api = FigmaApi::from_token(&Some(figma_personal_access_token)).unwrap();
 /// let document = api.get_document(&file_id).unwrap();
 /// println!("{}", document);
 /// ```
 pub struct FigmaApi {
-    client: Client,
+    client: RefCell<Client>,
+    /// OAuth credentials to refresh the access token with, if the client uses OAuth
+    oauth: RefCell<Option<OAuthCredentials>>,
 }
 
 impl ImageFormat {
@@ -58,8 +108,10 @@ impl ImageFormat {
     }
 }
 
+pub const FIGMA_API_URL: &str = "https://api.figma.com/";
 pub const FIGMA_FILES_ENDPOINT: &str = "https://api.figma.com/v1/files/";
 pub const FIGMA_IMAGES_ENDPOINT: &str = "https://api.figma.com/v1/images/";
+pub const FIGMA_ME_ENDPOINT: &str = "https://api.figma.com/v1/me";
 
 impl FigmaApi {
     /// Create new `FigmaApi` instance to make requests to Figma API endpoints.
@@ -68,12 +120,34 @@ impl FigmaApi {
     ///
     /// * `client` - An instance of [reqwest::blocking::Client] to make requests with.
     pub fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client: RefCell::new(client),
+            oauth: RefCell::new(None),
+        }
     }
 
-    /// Load Figma [Document] from the cache if the cache is not empty. Otherwise,
-    /// load the document from the remote. Returns a tuple ([Document], bool), where
-    /// bool value is true, if the document have loaded from cache.
+    /// Create new `FigmaApi` instance with the token from the arguments, or with the token
+    /// saved by `fxa auth login` or `fxa auth oauth`. Expired OAuth access token is refreshed.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - Figma personal access token from the arguments, if any.
+    pub fn from_token(token: &Option<String>) -> Result<Self, AppError> {
+        let (figma_token, _) = resolve_token(token)?;
+        let api = FigmaApi::new(create_http_client(&figma_token)?);
+        if let FigmaToken::OAuth(credentials) = figma_token {
+            let expired = credentials.is_expired();
+            *api.oauth.borrow_mut() = Some(credentials);
+            if expired {
+                api.refresh_oauth_token()?;
+            }
+        }
+        Ok(api)
+    }
+
+    /// Load Figma file with its [Document] from the cache if the cache is not empty. Otherwise,
+    /// load the file from the remote. Returns a tuple ([FigmaGetFileResponse], bool), where
+    /// bool value is true, if the file have loaded from cache.
     ///
     /// Endpoint: `https://api.figma.com/v1/files/:file_key`
     ///
@@ -81,24 +155,96 @@ impl FigmaApi {
     ///
     /// * `file_id` - Figma file identifier. To obtain a file id, open the file in the browser.
     /// The file id will be present in the URL after the word file and before the file name.
-    pub fn get_document(&self, file_id: &String) -> Result<(Document, bool), AppError> {
+    pub fn get_document(&self, file_id: &String) -> Result<(FigmaGetFileResponse, bool), AppError> {
         load_from_cache::<FigmaGetFileResponse>(&file_id)
-            .map(|response| (response.document, true))
+            .map(|response| (response, true))
             .or_else(|_| {
                 let url = format!("{}{}", FIGMA_FILES_ENDPOINT, &file_id);
-                let response = self.client.get(&url).send();
-                match_response_internal(response, &url, |response| {
-                    match response.json::<FigmaGetFileResponse>() {
+                self.match_response_internal(
+                    &url,
+                    |client| client.get(&url),
+                    |response| match response.json::<FigmaGetFileResponse>() {
                         Ok(response) => {
                             save_to_cache(&response, &file_id).unwrap_or_default();
-                            Ok((response.document, false))
+                            Ok((response, false))
                         }
                         Err(_) => Err(AppError::FetchDomResponseParsing(url.clone())),
-                    }
-                })
+                    },
+                )
             })
     }
 
+    /// Get the user the token belongs to. Use it to check the token.
+    ///
+    /// Endpoint: `https://api.figma.com/v1/me`
+    pub fn get_me(&self) -> Result<FigmaUser, AppError> {
+        let url = FIGMA_ME_ENDPOINT.to_string();
+        self.match_response_internal(
+            &url,
+            |client| client.get(&url),
+            |response| {
+                response
+                    .json::<FigmaUser>()
+                    .map_err(|_| AppError::GetMeResponseParsing(url.clone()))
+            },
+        )
+    }
+
+    /// Get the current version of Figma file without loading the whole document.
+    ///
+    /// Endpoint: `https://api.figma.com/v1/files/:file_key?depth=1`
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - Figma file identifier.
+    pub fn get_file_version(&self, file_id: &String) -> Result<String, AppError> {
+        let url = format!("{}{}", FIGMA_FILES_ENDPOINT, &file_id);
+        let request = |client: &Client| client.get(&url).query(&[("depth", 1)]);
+        self.match_response_internal(&url, request, |response| match response
+            .json::<FigmaGetFileVersionResponse>(
+        ) {
+            Ok(response) => Ok(response.version),
+            Err(_) => Err(AppError::FetchDomResponseParsing(url.clone())),
+        })
+    }
+
+    /// Load a single node of Figma file with its children, without loading the whole document.
+    /// Returns a tuple ([Frame], Option<String>) with the node and the version of the file.
+    ///
+    /// Endpoint: `https://api.figma.com/v1/files/:file_key/nodes?ids=:node_id`
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - Figma file identifier.
+    /// * `node_id` - node identifier inside Figma file, e.g. `12:34`.
+    pub fn get_node(
+        &self,
+        file_id: &String,
+        node_id: &String,
+    ) -> Result<(Frame, Option<String>), AppError> {
+        let url = format!("{}{}/nodes", FIGMA_FILES_ENDPOINT, &file_id);
+        let request = |client: &Client| client.get(&url).query(&[("ids", node_id)]);
+        self.match_response_internal(&url, request, |response| {
+            let response = response
+                .json::<FigmaGetFileNodesResponse>()
+                .map_err(|_| AppError::FetchDomResponseParsing(url.clone()))?;
+            let version = response.version;
+            match response.nodes.into_iter().find(|(id, _)| id == node_id) {
+                Some((_, Some(node))) => Ok((node.document, version)),
+                _ => Err(AppError::NodeNotFound(node_id.clone(), file_id.clone())),
+            }
+        })
+    }
+
+    /// Remove the cached document of Figma file, so it is loaded from the remote next time.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - Figma file identifier.
+    pub fn invalidate_document(&self, file_id: &String) -> Result<(), AppError> {
+        remove_from_cache(file_id)
+    }
+
     /// Get url of exported Figma frame to download.
     ///
     /// Endpoint: `https://api.figma.com/v1/images/:file_key`
@@ -120,14 +266,14 @@ impl FigmaApi {
         format: &ImageFormat,
     ) -> Result<String, AppError> {
         let url = format!("{}{}", FIGMA_IMAGES_ENDPOINT, &file_id);
-        let response = self
-            .client
-            .get(&url)
-            .query(&[("ids", node_id.clone())])
-            .query(&[("scale", scale)])
-            .query(&[("format", format.download_extension())])
-            .send();
-        match_response_internal(response, &url, |response| {
+        let request = |client: &Client| {
+            client
+                .get(&url)
+                .query(&[("ids", node_id.clone())])
+                .query(&[("scale", scale)])
+                .query(&[("format", format.download_extension())])
+        };
+        self.match_response_internal(&url, request, |response| {
             match response.json::<FigmaGetImageResponse>() {
                 Ok(response) => Ok(response.images.get(node_id).unwrap().clone()), // todo: unwrap safe
                 Err(_) => Err(AppError::GetImageDownloadUrl(url.clone())),
@@ -135,54 +281,101 @@ impl FigmaApi {
         })
     }
 
-    /// Download an image from remote.
+    /// Download an image from remote into memory.
     ///
     /// # Arguments
     ///
     /// * `image_url` - Url to download image.
-    /// * `image_name` - The name with which the image will be saved to the temporary directory.
-    /// * `image_scale_name` - _Optional_. Will be used as image name suffix.
-    /// * `image_format` - Format with which the image will be saved to the temporary directory.
-    pub fn get_image(
+    pub fn get_image(&self, image_url: &String) -> Result<Vec<u8>, AppError> {
+        self.match_response_internal(
+            image_url,
+            |client| client.get(image_url),
+            |response| {
+                response
+                    .bytes()
+                    .map(|bytes| bytes.to_vec())
+                    .map_err(|_| AppError::GetImageByteStream)
+            },
+        )
+    }
+
+    /// Send the request and handle the response. If Figma API rejects OAuth access token,
+    /// the token is refreshed and the request is sent again.
+    fn match_response_internal<T, R, F>(
         &self,
-        image_url: &String,
-        image_name: &String,
-        image_scale_name: &String,
-        image_format: &ImageFormat,
-    ) -> Result<String, AppError> {
-        let response = self.client.get(image_url).send();
-        match_response_internal(response, &image_url, |response| {
-            let bytes = response.bytes().map_err(|_| AppError::GetImageByteStream)?;
-            create_temp_dir().map_err(|_| AppError::CreateTempDir)?;
-            let image_file_name = format!(
-                "{}/{}_{}.{}",
-                TEMP_DIR_PATH,
-                &image_name,
-                &image_scale_name,
-                image_format.download_extension(),
-            );
-            fs::write(&image_file_name, bytes)
-                .map_err(|_| AppError::GetImageTemporarySave)
-                .map(|_| image_file_name)
-        })
+        url: &str,
+        request: R,
+        on_success: F,
+    ) -> Result<T, AppError>
+    where
+        R: Fn(&Client) -> RequestBuilder,
+        F: FnOnce(Response) -> Result<T, AppError>,
+    {
+        // Image urls point to the storage, which doesn't accept Figma tokens at all
+        let refresh = if url.starts_with(FIGMA_API_URL) && self.oauth.borrow().is_some() {
+            Some(|| self.refresh_oauth_token())
+        } else {
+            None
+        };
+        let response = send_with_token_refresh(&self.client, url, request, refresh)?;
+
+        match response.status() {
+            StatusCode::OK => on_success(response),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                Err(AppError::RequestUnauthorized(response.status()))
+            }
+            _ => Err(AppError::RequestHttpStatus(
+                url.to_string(),
+                response.status(),
+            )),
+        }
+    }
+
+    /// Get new OAuth access token, save it to the credentials file and use it for the next requests.
+    fn refresh_oauth_token(&self) -> Result<(), AppError> {
+        let mut oauth = self.oauth.borrow_mut();
+        let credentials = oauth.as_mut().expect("Client uses OAuth");
+        let response = refresh_access_token(credentials)?;
+        credentials.update(response);
+
+        let mut saved_credentials = Credentials::from_file()?;
+        saved_credentials.oauth = Some(credentials.clone());
+        saved_credentials.save()?;
+
+        *self.client.borrow_mut() = create_http_client(&FigmaToken::OAuth(credentials.clone()))?;
+        Ok(())
     }
 }
 
-fn match_response_internal<T, F>(
-    response: Result<Response, Error>,
-    url: &String,
-    on_success: F,
-) -> Result<T, AppError>
+/// Send the request. If it is rejected as unauthorized and the token can be refreshed,
+/// the request is sent again with the new token.
+fn send_with_token_refresh<R, F>(
+    client: &RefCell<Client>,
+    url: &str,
+    request: R,
+    refresh: Option<F>,
+) -> Result<Response, AppError>
 where
-    F: FnOnce(Response) -> Result<T, AppError>,
+    R: Fn(&Client) -> RequestBuilder,
+    F: FnOnce() -> Result<(), AppError>,
 {
-    match response {
-        Ok(response) => match response.status() {
-            StatusCode::OK => on_success(response),
-            StatusCode::FORBIDDEN => Err(AppError::RequestUnauthorized(response.status())),
-            _ => Err(AppError::RequestHttpStatus(url.clone(), response.status())),
-        },
-        Err(_) => Err(AppError::RequestMaybeVPN(url.clone())),
+    let send = || {
+        request(&client.borrow())
+            .send()
+            .map_err(|_| AppError::RequestMaybeVPN(url.to_string()))
+    };
+    let response = send()?;
+    match refresh {
+        Some(refresh)
+            if matches!(
+                response.status(),
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+            ) =>
+        {
+            refresh()?;
+            send()
+        }
+        _ => Ok(response),
     }
 }
 
@@ -204,3 +397,80 @@ fn save_to_cache<T: Serialize>(value: T, id: &String) -> Result<(), AppError> {
         Err(_) => Err(AppError::CreateTempDir),
     }
 }
+
+fn remove_from_cache(id: &String) -> Result<(), AppError> {
+    let file_name = format!("{}/cache_{}.json", TEMP_DIR_PATH, &id);
+    match std::fs::remove_file(&file_name) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(_) => Err(AppError::RemoveFromCache),
+    }
+}
+
+#[test]
+fn test_send_with_token_refresh() {
+    use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+    use std::io::{BufRead, Write};
+    use std::net::TcpListener;
+
+    // Responds with the statuses one by one and returns `Authorization` headers of the requests
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let url = format!("http://{}/v1/me", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let mut authorizations = vec![];
+        for status in ["401 Unauthorized", "200 OK", "403 Forbidden"] {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut authorization = String::new();
+            for line in BufReader::new(&stream).lines() {
+                let line = line.unwrap();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("authorization") {
+                        authorization = value.trim().to_string();
+                    }
+                }
+            }
+            authorizations.push(authorization);
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            )
+            .unwrap();
+        }
+        authorizations
+    });
+    let client_with_token = |token: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+        );
+        Client::builder().default_headers(headers).build().unwrap()
+    };
+    let client = RefCell::new(client_with_token("expired"));
+
+    let refreshed = send_with_token_refresh(
+        &client,
+        &url,
+        |client| client.get(&url),
+        Some(|| {
+            *client.borrow_mut() = client_with_token("fresh");
+            Ok(())
+        }),
+    )
+    .unwrap();
+    assert_eq!(refreshed.status(), StatusCode::OK);
+
+    let not_refreshed =
+        send_with_token_refresh(&client, &url, |client| client.get(&url), None::<fn() -> _>)
+            .unwrap();
+    assert_eq!(not_refreshed.status(), StatusCode::FORBIDDEN);
+
+    assert_eq!(
+        server.join().unwrap(),
+        vec!["Bearer expired", "Bearer fresh", "Bearer fresh"]
+    );
+}
@@ -1 +1,2 @@
 pub mod figma;
+pub mod oauth;
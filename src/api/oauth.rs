@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use reqwest::blocking::Client;
+use reqwest::{StatusCode, Url};
+
+use crate::common::error::AppError;
+use crate::models::credentials::{OAuthCredentials, OAuthTokenResponse};
+
+pub const FIGMA_OAUTH_URL: &str = "https://www.figma.com/oauth";
+pub const FIGMA_OAUTH_TOKEN_ENDPOINT: &str = "https://api.figma.com/v1/oauth/token";
+pub const FIGMA_OAUTH_REFRESH_ENDPOINT: &str = "https://api.figma.com/v1/oauth/refresh";
+
+/// Url of Figma page where the user allows the app to access the files.
+///
+/// Details: https://www.figma.com/developers/api#oauth2
+///
+/// # Arguments
+///
+/// * `client_id` - Client id of Figma OAuth app
+/// * `redirect_uri` - Callback url, Figma redirects to it with the code after the authorization
+/// * `scope` - Comma separated scopes of the access
+/// * `state` - Random string, which is returned with the code to check the redirect
+pub fn authorization_url(client_id: &str, redirect_uri: &str, scope: &str, state: &str) -> String {
+    Url::parse_with_params(
+        FIGMA_OAUTH_URL,
+        &[
+            ("client_id", client_id),
+            ("redirect_uri", redirect_uri),
+            ("scope", scope),
+            ("state", state),
+            ("response_type", "code"),
+        ],
+    )
+    .expect("OAuth url is valid")
+    .to_string()
+}
+
+/// Exchange the code, received after the authorization, for access and refresh tokens.
+///
+/// Endpoint: `https://api.figma.com/v1/oauth/token`
+pub fn exchange_code(
+    client_id: &str,
+    client_secret: &str,
+    redirect_uri: &str,
+    code: &str,
+) -> Result<OAuthTokenResponse, AppError> {
+    request_tokens(
+        FIGMA_OAUTH_TOKEN_ENDPOINT,
+        &[
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("redirect_uri", redirect_uri),
+            ("code", code),
+            ("grant_type", "authorization_code"),
+        ],
+    )
+}
+
+/// Get new access token with the refresh token.
+///
+/// Endpoint: `https://api.figma.com/v1/oauth/refresh`
+pub fn refresh_access_token(
+    credentials: &OAuthCredentials,
+) -> Result<OAuthTokenResponse, AppError> {
+    request_tokens(
+        FIGMA_OAUTH_REFRESH_ENDPOINT,
+        &[
+            ("client_id", credentials.client_id.as_str()),
+            ("client_secret", credentials.client_secret.as_str()),
+            ("refresh_token", credentials.refresh_token.as_str()),
+        ],
+    )
+}
+
+fn request_tokens(url: &str, params: &[(&str, &str)]) -> Result<OAuthTokenResponse, AppError> {
+    let oauth_error = |cause: String| AppError::OAuthTokenRequest(url.to_string(), cause);
+    let client = Client::builder()
+        .timeout(Some(Duration::new(15, 0)))
+        .build()
+        .map_err(|e| AppError::CannotCreateHttpClient(e.to_string()))?;
+    let response = client
+        .post(url)
+        .form(params)
+        .send()
+        .map_err(|_| AppError::RequestMaybeVPN(url.to_string()))?;
+    match response.status() {
+        StatusCode::OK => response
+            .json::<OAuthTokenResponse>()
+            .map_err(|e| oauth_error(e.to_string())),
+        status => Err(oauth_error(format!(
+            "{}. Run `fxa auth oauth` to authorize again",
+            status
+        ))),
+    }
+}
@@ -30,6 +30,38 @@ pub enum AppError {
     )]
     AppConfigInvalidMainResImages(String),
 
+    #[error(
+        "To export colors, you must specify `android.colors.mainRes` or `android.mainRes` in {0}"
+    )]
+    AppConfigInvalidMainResColors(String),
+
+    #[error("Invalid route to `{1}` in {0}: {2}")]
+    AppConfigInvalidRoute(String, String, String),
+
+    #[error("Invalid `collect.variantNameTemplate` `{1}` in {0}: {2}")]
+    AppConfigInvalidVariantTemplate(String, String, String),
+
+    #[error("To export resources, you must specify `figma.fileId` or `figma.sources` in {0}")]
+    AppConfigMissingFileId(String),
+
+    #[error("There are no `targets` to export in {0}")]
+    AppConfigMissingTargets(String),
+
+    #[error("Target `{0}` has no `fileId`, and there is no `figma.fileId` in the config")]
+    TargetMissingFileId(String),
+
+    #[error("Target `{0}` can't be exported in format {1}")]
+    TargetInvalidFormat(String, String),
+
+    #[error("Colors targets `{1}` and `{2}` are both written to {0}, set another `mainRes` for one of them")]
+    ConflictingColorsTargets(String, String, String),
+
+    #[error("There is no target `{0}` in the config. Available targets: {1}")]
+    UnknownTarget(String, String),
+
+    #[error("Export of {0} targets failed")]
+    TargetsFailed(usize),
+
     #[error("Cannot parse json response from Figma API ({0}).")]
     FetchDomResponseParsing(String),
 
@@ -39,8 +71,8 @@ pub enum AppError {
     #[error("Can't get image byte stream. This error shouldn't have happened. Report it to the developer.")]
     GetImageByteStream,
 
-    #[error("Can't save downloaded image into temp directory. Maybe something is wrong with the app permissions?")]
-    GetImageTemporarySave,
+    #[error("Can't save temporary file {0}. Cause: {1}")]
+    CannotSaveTemporaryFile(String, String),
 
     #[error(
         "Can't create temporary dirictory. Maybe something is wrong with the app permissions?"
@@ -50,7 +82,7 @@ pub enum AppError {
     #[error("Can't access remote source {0}, {1}")]
     RequestHttpStatus(String, StatusCode),
 
-    #[error("Invalid Figma personal access token: {0}")]
+    #[error("Invalid Figma token: {0}. Check it with `fxa auth status`")]
     RequestUnauthorized(StatusCode),
 
     #[error(
@@ -65,41 +97,139 @@ pub enum AppError {
     #[error("Can't save to cache")]
     SaveToCache,
 
+    #[error("Can't remove outdated cache entry")]
+    RemoveFromCache,
+
     #[error("Can't find find frame with name `{0}`. Make sure such a frame exists.")]
     FindDesiredFrame(String),
 
+    #[error("There are several frames with name `{0}`: {1}. Specify the path to the desired one, e.g. `Page/Section/{0}`, or rename the others.")]
+    AmbiguousFrame(String, String),
+
+    #[error("Several Figma nodes are exported with the same resource name:\n{0}\nRename them in Figma, or set `collect.duplicates` to `keepFirst` or `numberSuffix` in the config.")]
+    DuplicateResourceNames(String),
+
+    #[error("Resources from different Figma sources are exported with the same resource name:\n{0}\nRename them in Figma or change `naming` in the config.")]
+    ConflictingSources(String),
+
+    #[error("Can't parse Figma link {0}: {1}. Copy it with `Copy link to selection` in Figma")]
+    InvalidFigmaUrl(String, String),
+
+    #[error("There is no node {0} in Figma file {1}")]
+    NodeNotFound(String, String),
+
+    #[error("Only one resource name can be given with `--url`, but there are {0}")]
+    SeveralNamesForUrl(usize),
+
+    #[error("`collect.variantNameTemplate` `{0}` has placeholder `{{{1}}}`, which is neither `{{set}}` nor a property of variant {2}. Fix the template in the config.")]
+    UnknownVariantProperty(String, String, String),
+
     #[error("Desired frame `{0}` has no child frames. Add some pictures there :)")]
     DesiredFrameIsEmpty(String),
 
     ///
-    /// WebP Converter
+    /// Auth
     ///
 
-    #[error("Can't open temporary image file {0}")]
-    SourceNotFound(String),
+    #[error("Figma token is not specified. Pass it with `-t`, set `FIGMA_PERSONAL_TOKEN` env variable or run `fxa auth login`")]
+    MissingToken,
+
+    #[error("Figma token contains invalid characters. Make sure it is copied correctly")]
+    InvalidToken,
+
+    #[error("Can't create http client. Cause: {0}")]
+    CannotCreateHttpClient(String),
+
+    #[error("Can't read credentials file {0}. Cause: {1}")]
+    CredentialsRead(String, String),
+
+    #[error("Can't write credentials file {0}. Cause: {1}")]
+    CredentialsWrite(String, String),
+
+    #[error("Cannot parse json response from Figma API ({0}).")]
+    GetMeResponseParsing(String),
+
+    #[error("Can't get OAuth tokens from {0}. Cause: {1}")]
+    OAuthTokenRequest(String, String),
 
-    #[error("Undelying reader error. Can't open temporary image file {0}")]
-    UnderlyingReader(String),
+    #[error("OAuth authorization failed. Cause: {0}")]
+    OAuthAuthorization(String),
 
-    #[error("Can't decode temporary image file {0}")]
-    CannotDecode(String),
+    ///
+    /// WebP Converter
+    ///
 
-    #[error("Can't encode temporary image file {0} because the image has unsupported color model (8-bit RGB and RGBA are supported now)")]
-    CannotEncode(String),
+    #[error("Can't decode image {0}. Cause: {1}")]
+    CannotDecode(String, String),
+
+    #[error("Can't encode image {0} to WEBP. Cause: {1}")]
+    CannotEncode(String, String),
+
+    ///
+    /// Local Rendering
+    ///
 
-    #[error("Can't write data to temporary webp image file. Cause: {0}")]
-    WriteWebpTemporarySave(String),
+    #[error("Can't render image {0} locally. Cause: {1}")]
+    CannotRenderImage(String, String),
 
     ///
     /// Export Common
     ///
 
-    #[error("Can't create res/drawable directory. Cause: {0}")]
-    CannotCreateDrawableDir(String),
+    #[error(
+        "Can't write exported resources to the project, all changes are rolled back. Cause: {0}"
+    )]
+    CannotCommitExport(String),
+
+    #[error("Can't write exported resources to the project ({0}), and can't roll back the changes: {1}. Check the project with `git status`")]
+    CannotRollbackExport(String, String),
+
+    #[error("Can't read lockfile {0}. Cause: {1}")]
+    LockfileRead(String, String),
+
+    #[error("Can't write lockfile {0}. Cause: {1}")]
+    LockfileWrite(String, String),
+
+    #[error("Can't delete stale file {0}. Cause: {1}")]
+    CannotDeleteStaleFile(String, String),
 
-    #[error("Can't move image {0} from temporary directory to drawable directory. Cause: {1}")]
-    CannotMoveToDrawableDir(String, String),
+    #[error("Can't listen for webhooks on port {0}. Cause: {1}")]
+    CannotStartWebhookServer(u16, String),
+
+    #[error("Can't write webhook summary to {0}. Cause: {1}")]
+    CannotWriteWebhookSummary(String, String),
+
+    #[error("Figma name `{0}` is converted to `{1}`, which is not a valid Android resource name: {2}. {3}")]
+    InvalidResName(String, String, String, String),
+
+    #[error("{0} resource names are invalid, so nothing is exported")]
+    InvalidResNames(usize),
+
+    #[error("Export of {0} resources failed, so nothing is written to the project. Use `--allow-partial` to write the rest")]
+    ExportAborted(usize),
 
     #[error("Can't convert svg to android vector drawable xml. Cause: {0}")]
     CannotConvertToXml(#[from] VectorDrawableError),
+
+    #[error("Can't verify converted icon {0}. Cause: {1}")]
+    CannotVerifyIcon(String, String),
+
+    #[error("Converted icon {0} differs from the original SVG by {1:.2}% (threshold is {2}%). See the difference: {3}")]
+    IconMismatch(String, f32, f32, String),
+
+    #[error("Figma node `{0}` has no visible solid fill, so it can't be exported as a color. Documents cached by older versions of the app have no fills, remove them with `fxa cleanup`")]
+    ColorWithoutFill(String),
+
+    ///
+    /// Convert
+    ///
+
+    #[error("Can't convert android vector drawable xml to svg. Cause: {0}")]
+    CannotConvertToSvg(VectorDrawableError),
+
+    #[error("Can't convert file {0} to {1}, expected file with extension `.{2}`")]
+    UnexpectedConvertSource(String, String, String),
+
+    #[error("Can't save converted file {0}. Cause: {1}")]
+    CannotSaveConverted(String, String),
 }
@@ -0,0 +1,385 @@
+use std::collections::{BTreeMap, HashSet};
+
+use regex::{Captures, Regex};
+
+use crate::common::error::AppError;
+use crate::common::res_name::to_res_name_with;
+use crate::models::config::{CollectConfig, DuplicatesRule, NamingConfig};
+use crate::models::figma::Frame;
+
+const COMPONENT_SET: &str = "COMPONENT_SET";
+
+/// Types of the nodes which are opened to look for resources inside, if the depth allows
+const GROUPING_TYPES: [&str; 3] = ["FRAME", "SECTION", "GROUP"];
+
+/// Resource found in the frame with its name for export.
+pub struct CollectedResource<'a> {
+    pub name: String,
+    pub node: &'a Frame,
+    /// Resource name which is given instead of the one made by the naming rules,
+    /// e.g. the name with a number suffix of a duplicate
+    pub res_name: Option<String>,
+}
+
+impl CollectedResource<'_> {
+    /// Resource name the resource is exported under.
+    pub fn res_name_with(&self, naming: &NamingConfig) -> String {
+        self.res_name
+            .clone()
+            .unwrap_or_else(|| to_res_name_with(&self.name, naming))
+    }
+}
+
+/// Collect resources from the frame.
+///
+/// Direct children of the frame are resources by default. With bigger `depth`, frames, sections
+/// and groups which are not of the collected `nodeTypes` are opened, and their children are
+/// collected too. With `splitVariants`, variants of component sets are collected as separate
+/// resources named by `variantNameTemplate`.
+pub fn collect_resources<'a>(
+    frame: &'a Frame,
+    config: &CollectConfig,
+) -> Result<Vec<CollectedResource<'a>>, AppError> {
+    let mut resources: Vec<CollectedResource> = Vec::new();
+    collect_children(frame, config, 1, &mut resources)?;
+    Ok(resources)
+}
+
+fn collect_children<'a>(
+    frame: &'a Frame,
+    config: &CollectConfig,
+    level: u32,
+    resources: &mut Vec<CollectedResource<'a>>,
+) -> Result<(), AppError> {
+    for node in frame.children.iter().flatten() {
+        let is_collected_type =
+            config.node_types.is_empty() || config.node_types.contains(&node.node_type);
+
+        if config.split_variants && node.node_type == COMPONENT_SET {
+            for variant in node.children.iter().flatten() {
+                if config.node_types.is_empty() || config.node_types.contains(&variant.node_type) {
+                    let name =
+                        variant_name(&node.name, &variant.name, &config.variant_name_template)
+                            .map_err(|placeholder| {
+                                AppError::UnknownVariantProperty(
+                                    config.variant_name_template.clone().unwrap_or_default(),
+                                    placeholder,
+                                    format!(
+                                        "`{}` ({}) of set `{}`",
+                                        variant.name, variant.id, node.name
+                                    ),
+                                )
+                            })?;
+                    resources.push(CollectedResource {
+                        name,
+                        node: variant,
+                        res_name: None,
+                    });
+                }
+            }
+            continue;
+        }
+
+        // Grouping nodes are resources themselves only if their type is listed explicitly
+        let is_grouping = GROUPING_TYPES.contains(&node.node_type.as_str())
+            && !config.node_types.contains(&node.node_type);
+        if level < config.depth && is_grouping && node.children.is_some() {
+            collect_children(node, config, level + 1, resources)?;
+        } else if is_collected_type {
+            resources.push(CollectedResource {
+                name: node.name.clone(),
+                node,
+                res_name: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Resources which are exported under the same resource name, with their names and node ids.
+pub struct Duplicate {
+    pub res_name: String,
+    pub resources: Vec<(String, String)>,
+}
+
+/// Find resources which are exported under the same name and disambiguate them by the rule.
+///
+/// Both nodes with the same Figma name and nodes with different names which are converted
+/// to the same resource name by the naming rules are duplicates. With `DuplicatesRule::Fail`
+/// the resources are returned as is, the caller decides how to report the duplicates.
+/// With `DuplicatesRule::NumberSuffix` only the resource names get the suffix, Figma names
+/// of the nodes stay as they are.
+pub fn resolve_duplicates<'a>(
+    resources: Vec<CollectedResource<'a>>,
+    rule: &DuplicatesRule,
+    naming: &NamingConfig,
+) -> (Vec<CollectedResource<'a>>, Vec<Duplicate>) {
+    let mut groups: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, resource) in resources.iter().enumerate() {
+        groups
+            .entry(to_res_name_with(&resource.name, naming))
+            .or_default()
+            .push(index);
+    }
+    let duplicates: Vec<(String, Vec<usize>)> = groups
+        .iter()
+        .filter(|(_, indices)| indices.len() > 1)
+        .map(|(res_name, indices)| (res_name.clone(), indices.clone()))
+        .collect();
+    let reported = duplicates
+        .iter()
+        .map(|(res_name, indices)| Duplicate {
+            res_name: res_name.clone(),
+            resources: indices
+                .iter()
+                .map(|&i| (resources[i].name.clone(), resources[i].node.id.clone()))
+                .collect(),
+        })
+        .collect();
+
+    let resources = match rule {
+        DuplicatesRule::Fail => resources,
+        DuplicatesRule::KeepFirst => {
+            let skipped: HashSet<usize> = duplicates
+                .iter()
+                .flat_map(|(_, indices)| indices[1..].iter().copied())
+                .collect();
+            resources
+                .into_iter()
+                .enumerate()
+                .filter(|(index, _)| !skipped.contains(index))
+                .map(|(_, resource)| resource)
+                .collect()
+        }
+        DuplicatesRule::NumberSuffix => {
+            let mut resources = resources;
+            let mut taken: HashSet<String> = groups.into_keys().collect();
+            for (res_name, indices) in &duplicates {
+                for &index in &indices[1..] {
+                    let suffixed = (2..)
+                        .map(|n| format!("{}_{}", res_name, n))
+                        .find(|suffixed| !taken.contains(suffixed))
+                        .unwrap();
+                    taken.insert(suffixed.clone());
+                    resources[index].res_name = Some(suffixed);
+                }
+            }
+            resources
+        }
+    };
+    (resources, reported)
+}
+
+/// Name of the component set variant. Variant names in Figma are lists of properties,
+/// e.g. `size=24, name=search`.
+///
+/// Without the template the name is the name of the set with the values of properties,
+/// e.g. `Icon_24_search`. With the template `ic_{size}_{name}` it is `ic_24_search`.
+/// Returns the placeholder of the template which is not a property of the variant as the error.
+fn variant_name(
+    set_name: &str,
+    variant_name: &str,
+    template: &Option<String>,
+) -> Result<String, String> {
+    let properties: Vec<(&str, &str)> = variant_name
+        .split(',')
+        .filter_map(|property| property.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect();
+    let template = match template {
+        Some(template) => template,
+        None if properties.is_empty() => return Ok(format!("{}_{}", set_name, variant_name)),
+        None => {
+            let values: Vec<&str> = properties.iter().map(|(_, value)| *value).collect();
+            return Ok(format!("{}_{}", set_name, values.join("_")));
+        }
+    };
+
+    let mut unknown: Option<String> = None;
+    let name = placeholder_regex().replace_all(template, |captures: &Captures| {
+        let placeholder = &captures[1];
+        if placeholder == "set" {
+            return set_name.to_string();
+        }
+        match properties
+            .iter()
+            .find(|(key, _)| *key == placeholder || key.to_lowercase() == placeholder)
+        {
+            Some((_, value)) => value.to_string(),
+            None => {
+                unknown.get_or_insert_with(|| placeholder.to_string());
+                String::new()
+            }
+        }
+    });
+    match unknown {
+        Some(placeholder) => Err(placeholder),
+        None => Ok(name.to_string()),
+    }
+}
+
+/// Check that the braces of `variantNameTemplate` are paired, before any variant is named.
+pub fn validate_variant_name_template(template: &str) -> Result<(), String> {
+    if placeholder_regex()
+        .replace_all(template, "")
+        .contains(['{', '}'])
+    {
+        return Err("braces must be paired around the names of placeholders".to_string());
+    }
+    Ok(())
+}
+
+/// Placeholder of `variantNameTemplate`, e.g. `{size}`
+fn placeholder_regex() -> Regex {
+    Regex::new(r"\{([^{}]+)\}").expect("Placeholder regex is valid")
+}
+
+#[test]
+fn test_collect_resources() {
+    let frame: Frame = serde_json::from_str(
+        r#"{
+            "id": "1:0", "name": "Icons", "type": "FRAME",
+            "children": [
+                { "id": "1:1", "name": "ic_24/close", "type": "COMPONENT" },
+                { "id": "1:2", "name": "Navigation", "type": "FRAME", "children": [
+                    { "id": "1:3", "name": "ic_24/back", "type": "COMPONENT" },
+                    { "id": "1:4", "name": "ic_24/back_usage", "type": "INSTANCE" }
+                ] },
+                { "id": "1:5", "name": "Icon", "type": "COMPONENT_SET", "children": [
+                    { "id": "1:6", "name": "Size=24, name=search", "type": "COMPONENT" },
+                    { "id": "1:7", "name": "Size=16, name=search", "type": "COMPONENT" }
+                ] }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let collect = |depth: u32, node_types: &[&str], template: Option<&str>| {
+        let config = CollectConfig {
+            depth,
+            node_types: node_types.iter().map(|t| t.to_string()).collect(),
+            split_variants: template.is_some(),
+            variant_name_template: template.map(|t| t.to_string()),
+            duplicates: DuplicatesRule::Fail,
+        };
+        collect_resources(&frame, &config)
+            .unwrap()
+            .into_iter()
+            .map(|r| format!("{} {}", r.node.id, r.name))
+            .collect::<Vec<String>>()
+    };
+
+    // Component sets are resources themselves unless the variants are split
+    assert_eq!(
+        collect(1, &[], None),
+        vec!["1:1 ic_24/close", "1:2 Navigation", "1:5 Icon"]
+    );
+    assert_eq!(
+        collect(2, &["COMPONENT"], Some("ic_{size}_{name}")),
+        vec![
+            "1:1 ic_24/close",
+            "1:3 ic_24/back",
+            "1:6 ic_24_search",
+            "1:7 ic_16_search"
+        ]
+    );
+    assert_eq!(
+        collect(2, &["INSTANCE", "COMPONENT_SET"], None),
+        vec!["1:4 ic_24/back_usage", "1:5 Icon"]
+    );
+
+    let config = CollectConfig {
+        depth: 1,
+        node_types: vec![],
+        split_variants: true,
+        variant_name_template: Some("ic_{size}_{style}".to_string()),
+        duplicates: DuplicatesRule::Fail,
+    };
+    assert!(matches!(
+        collect_resources(&frame, &config),
+        Err(AppError::UnknownVariantProperty(_, placeholder, _)) if placeholder == "style"
+    ));
+}
+
+#[test]
+fn test_variant_name() {
+    let name = |template: Option<&str>| {
+        variant_name(
+            "Icon",
+            "Size=24, name=search",
+            &template.map(|t| t.to_string()),
+        )
+    };
+
+    assert_eq!(name(None).unwrap(), "Icon_24_search");
+    assert_eq!(name(Some("ic_{size}_{name}")).unwrap(), "ic_24_search");
+    assert_eq!(name(Some("{set}_{Size}")).unwrap(), "Icon_24");
+    assert_eq!(name(Some("ic_{size}_{color}")), Err("color".to_string()));
+    assert!(validate_variant_name_template("ic_{size}_{name}").is_ok());
+    assert!(validate_variant_name_template("ic_{size_{name}").is_err());
+    assert!(validate_variant_name_template("ic_size}").is_err());
+}
+
+#[test]
+fn test_resolve_duplicates() {
+    let frame: Frame = serde_json::from_str(
+        r#"{
+            "id": "1:0", "name": "Icons", "type": "FRAME",
+            "children": [
+                { "id": "1:1", "name": "ic_24/search", "type": "COMPONENT" },
+                { "id": "1:2", "name": "ic_close", "type": "COMPONENT" },
+                { "id": "1:3", "name": "ic_24/search", "type": "COMPONENT" },
+                { "id": "1:4", "name": "ic 24 search", "type": "COMPONENT" },
+                { "id": "1:5", "name": "ic_24_search_2", "type": "COMPONENT" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let config = CollectConfig {
+        depth: 1,
+        node_types: vec![],
+        split_variants: false,
+        variant_name_template: None,
+        duplicates: DuplicatesRule::Fail,
+    };
+    let naming = NamingConfig::default();
+    let resolve = |rule: DuplicatesRule| {
+        let (resources, duplicates) =
+            resolve_duplicates(collect_resources(&frame, &config).unwrap(), &rule, &naming);
+        let names = resources
+            .into_iter()
+            .map(|r| format!("{} {} {}", r.node.id, r.name, r.res_name_with(&naming)))
+            .collect::<Vec<String>>();
+        (names, duplicates)
+    };
+
+    let (names, duplicates) = resolve(DuplicatesRule::Fail);
+    assert_eq!(names.len(), 5);
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].res_name, "ic_24_search");
+    assert_eq!(
+        duplicates[0].resources,
+        vec![
+            ("ic_24/search".to_string(), "1:1".to_string()),
+            ("ic_24/search".to_string(), "1:3".to_string()),
+            ("ic 24 search".to_string(), "1:4".to_string()),
+        ]
+    );
+    assert_eq!(
+        resolve(DuplicatesRule::KeepFirst).0,
+        vec![
+            "1:1 ic_24/search ic_24_search",
+            "1:2 ic_close ic_close",
+            "1:5 ic_24_search_2 ic_24_search_2"
+        ]
+    );
+    assert_eq!(
+        resolve(DuplicatesRule::NumberSuffix).0,
+        vec![
+            "1:1 ic_24/search ic_24_search",
+            "1:2 ic_close ic_close",
+            "1:3 ic_24/search ic_24_search_3",
+            "1:4 ic 24 search ic_24_search_4",
+            "1:5 ic_24_search_2 ic_24_search_2"
+        ]
+    );
+}
@@ -1,119 +1,518 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 
-use crate::api::figma::{FigmaApi, FIGMA_FILES_ENDPOINT};
-use crate::models::figma::Frame;
-use crate::models::{config::AppConfig, figma::Document};
+use crate::api::figma::{FigmaApi, FigmaGetFileResponse, FIGMA_FILES_ENDPOINT};
+use crate::models::config::{AppConfig, DuplicatesRule, ResourceType};
+use crate::models::figma::{Color, Document, Frame, Rectangle};
+use crate::models::lockfile::content_hash;
 
 use crate::common::error::AppError;
+use crate::common::fetching::collector::{
+    collect_resources, resolve_duplicates, validate_variant_name_template, CollectedResource,
+    Duplicate,
+};
+use crate::common::fetching::node_url::parse_figma_url;
 use crate::common::fetching::view::View;
 use crate::common::renderer::Renderer;
+use crate::common::suffixes::SuffixExt;
 
 pub struct FetcherEntry {
     pub app_config: AppConfig,
-    pub document: Document,
     pub from_cache: bool,
     pub image_names_to_ids: HashMap<String, String>,
+    /// Ids of Figma files the resources are exported from, by their names
+    pub image_names_to_files: HashMap<String, String>,
+    /// Bounds of the resources by their names, because nodes of different files may have
+    /// the same ids
+    pub image_names_to_bounds: HashMap<String, Rectangle>,
+    /// Solid fill colors of the resources by their names, which are exported as color resources
+    pub image_names_to_colors: HashMap<String, Color>,
+    /// Res names given by the fetcher instead of the naming rules, by names, e.g. to
+    /// duplicates with number suffixes
+    pub image_names_to_res_names: HashMap<String, String>,
+    /// Versions of the Figma files by their ids, if they are known
+    pub file_versions: HashMap<String, Option<String>>,
+    /// Hash of the config file content, to notice changes of the export settings
+    pub config_hash: String,
+    /// Link to the node if it is fetched with `--url` instead of from the frames
+    pub node_url: Option<String>,
+    /// Name of the `fxa export` target if the resources are fetched from its frame
+    pub target: Option<String>,
 }
 
+#[derive(Clone, Copy)]
 pub enum FetcherTarget {
     Images,
     Icons,
+    Colors,
 }
 
+impl FetcherTarget {
+    fn resource_type(&self) -> ResourceType {
+        match self {
+            FetcherTarget::Images => ResourceType::Images,
+            FetcherTarget::Icons => ResourceType::Icons,
+            FetcherTarget::Colors => ResourceType::Colors,
+        }
+    }
+}
+
+/// Resource from one of the sources, to detect conflicts between the sources.
+struct SourcedResource {
+    name: String,
+    file_id: String,
+    node_id: String,
+}
+
+/// Figma files which are already fetched, by their ids, with `true` if they are loaded from cache.
+pub type FetchedDocuments = HashMap<String, (FigmaGetFileResponse, bool)>;
+
+/// Read the config and fetch all Figma sources of the target.
 pub fn fetch(
     api: &FigmaApi,
     yaml_config_path: &String,
     fetcher_target: FetcherTarget,
+    requested_names: &[String],
     renderer: &Renderer,
 ) -> Result<FetcherEntry, AppError> {
+    let (app_config, config_hash) = read_config(yaml_config_path, renderer)?;
+    if app_config
+        .sources(&fetcher_target.resource_type())
+        .is_empty()
+    {
+        return Err(AppError::AppConfigMissingFileId(yaml_config_path.clone()));
+    }
+    fetch_sources(
+        api,
+        app_config,
+        config_hash,
+        fetcher_target,
+        requested_names,
+        &mut FetchedDocuments::new(),
+        renderer,
+    )
+}
+
+/// Fetch all Figma sources of the target and merge the resources found in them.
+///
+/// Each file is fetched and cached separately, and only once for all calls with the same
+/// `documents`. Resources from different sources which are exported under the same resource
+/// name are reported as conflicts. Duplicates are reported if any of them is requested
+/// by `requested_names` (all resources if it is empty), conflicts only among the requested
+/// resources, the others are kept for suggestions.
+pub fn fetch_sources(
+    api: &FigmaApi,
+    app_config: AppConfig,
+    config_hash: String,
+    fetcher_target: FetcherTarget,
+    requested_names: &[String],
+    documents: &mut FetchedDocuments,
+    renderer: &Renderer,
+) -> Result<FetcherEntry, AppError> {
+    let sources = app_config.sources(&fetcher_target.resource_type());
+    let (collect_config, naming) = match fetcher_target {
+        FetcherTarget::Images => (
+            &app_config.common.images.collect,
+            &app_config.common.images.naming,
+        ),
+        FetcherTarget::Icons => (
+            &app_config.common.icons.collect,
+            &app_config.common.icons.naming,
+        ),
+        FetcherTarget::Colors => (
+            &app_config.common.colors.collect,
+            &app_config.common.colors.naming,
+        ),
+    };
+
+    let mut all_from_cache = true;
+    let mut names_to_ids: HashMap<String, String> = HashMap::new();
+    let mut names_to_files: HashMap<String, String> = HashMap::new();
+    let mut names_to_bounds: HashMap<String, Rectangle> = HashMap::new();
+    let mut names_to_colors: HashMap<String, Color> = HashMap::new();
+    let mut names_to_res_names: HashMap<String, String> = HashMap::new();
+    let mut file_versions: HashMap<String, Option<String>> = HashMap::new();
+    let mut res_names: HashMap<String, SourcedResource> = HashMap::new();
+    let mut conflicts: Vec<String> = Vec::new();
+    for source in &sources {
+        let file_id = &source.file_id;
+        let document_url = format!("{}{}", FIGMA_FILES_ENDPOINT, file_id);
+        renderer.render(View::FetchingDom {
+            url: document_url.clone(),
+        });
+        if !documents.contains_key(file_id) {
+            documents.insert(file_id.clone(), fetch_dom(api, file_id)?);
+        }
+        let (file, from_cache) = &documents[file_id];
+        all_from_cache &= from_cache;
+        renderer.render(View::DomFetched {
+            url: document_url.clone(),
+            from_cache: *from_cache,
+        });
+
+        renderer.render(View::ProcessingDom);
+        let desired_frame_name = &source.figma_frame_name;
+        let frame = find_images_frame(&file.document, &source.page_name, desired_frame_name)?;
+        // Duplicates are found in the whole frame, so a requested resource doesn't overwrite
+        // the file of another one, and number suffixes don't depend on the requested names
+        let (resources, duplicates) = resolve_duplicates(
+            collect_resources(frame, collect_config)?,
+            &collect_config.duplicates,
+            naming,
+        );
+        let (requested, others): (Vec<_>, Vec<_>) = resources
+            .into_iter()
+            .partition(|resource| is_requested(&resource_key(resource), requested_names));
+        let requested_ids: HashSet<&String> =
+            requested.iter().map(|resource| &resource.node.id).collect();
+        let duplicates: Vec<Duplicate> = duplicates
+            .into_iter()
+            .filter(|d| d.resources.iter().any(|(_, id)| requested_ids.contains(id)))
+            .collect();
+        if !duplicates.is_empty() {
+            if collect_config.duplicates == DuplicatesRule::Fail {
+                return Err(AppError::DuplicateResourceNames(
+                    duplicates
+                        .iter()
+                        .map(|d| describe_duplicate(file_id, d))
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                ));
+            }
+            for duplicate in &duplicates {
+                renderer.render(View::DuplicatesResolved(
+                    describe_duplicate(file_id, duplicate),
+                    collect_config.duplicates.clone(),
+                ));
+            }
+        }
+
+        for resource in &requested {
+            let res_name = resource.res_name_with(naming);
+            if let Some(other) = res_names.get(&res_name) {
+                conflicts.push(format!(
+                    "`{}`: {}, {}",
+                    res_name,
+                    describe_node(&other.file_id, &other.name, &other.node_id),
+                    describe_node(file_id, &resource.name, &resource.node.id),
+                ));
+                continue;
+            }
+            res_names.insert(
+                res_name,
+                SourcedResource {
+                    name: resource.name.clone(),
+                    file_id: file_id.clone(),
+                    node_id: resource.node.id.clone(),
+                },
+            );
+            let key = resource_key(resource);
+            if let Some(res_name) = &resource.res_name {
+                names_to_res_names.insert(key.clone(), res_name.clone());
+            }
+            names_to_ids.insert(key.clone(), resource.node.id.clone());
+            names_to_files.insert(key.clone(), file_id.clone());
+            // Bounding boxes are missing in documents cached by older versions of the app
+            if let Some(bounds) = resource.node.render_bounds() {
+                names_to_bounds.insert(key.clone(), bounds.clone());
+            }
+            if let Some(color) = resource.node.solid_color() {
+                names_to_colors.insert(key, color);
+            }
+        }
+        // Not requested resources are only suggested for the missing names
+        for resource in &others {
+            let key = resource_key(resource);
+            if let Some(res_name) = &resource.res_name {
+                names_to_res_names
+                    .entry(key.clone())
+                    .or_insert_with(|| res_name.clone());
+            }
+            names_to_ids
+                .entry(key)
+                .or_insert_with(|| resource.node.id.clone());
+        }
+        file_versions.insert(file_id.clone(), file.version.clone());
+
+        renderer.render(View::FoundImages {
+            frame_name: desired_frame_name.clone(),
+        });
+    }
+    if !conflicts.is_empty() {
+        return Err(AppError::ConflictingSources(conflicts.join("\n")));
+    }
+
+    Ok(FetcherEntry {
+        app_config,
+        from_cache: all_from_cache,
+        image_names_to_ids: names_to_ids,
+        image_names_to_files: names_to_files,
+        image_names_to_bounds: names_to_bounds,
+        image_names_to_colors: names_to_colors,
+        image_names_to_res_names: names_to_res_names,
+        file_versions,
+        config_hash,
+        node_url: None,
+        target: None,
+    })
+}
+
+/// Name the resource is known by in the fetcher entry: its Figma name, or the given res name
+/// for the duplicates whose Figma name is taken by the first of them.
+fn resource_key(resource: &CollectedResource) -> String {
+    resource
+        .res_name
+        .clone()
+        .unwrap_or_else(|| resource.name.clone())
+}
+
+/// Returns `true` if the resource is requested by the user, also with `_light` or `_dark` suffix.
+/// Every resource is requested if there are no requested names.
+fn is_requested(name: &String, requested_names: &[String]) -> bool {
+    requested_names.is_empty()
+        || requested_names.contains(name)
+        || requested_names.contains(&name.without_theme_suffix())
+}
+
+/// Fetch the single node from the link instead of looking for the resources in the frame.
+/// The node is exported from the file of the link, with the given name or with its own name.
+///
+/// # Arguments
+///
+/// * `url` - link to the node copied from Figma
+/// * `names` - names given by the user, there may be no more than one
+pub fn fetch_node(
+    api: &FigmaApi,
+    yaml_config_path: &String,
+    url: &str,
+    names: &[String],
+    renderer: &Renderer,
+) -> Result<FetcherEntry, AppError> {
+    if names.len() > 1 {
+        renderer.new_line();
+        return Err(AppError::SeveralNamesForUrl(names.len()));
+    }
+    let (app_config, config_hash) = read_config(yaml_config_path, renderer)?;
+    let node_url = parse_figma_url(url)?;
+
+    renderer.render(View::FetchingNode {
+        url: url.to_string(),
+    });
+    let (node, file_version) = api.get_node(&node_url.file_id, &node_url.node_id)?;
+    let name = names.first().unwrap_or(&node.name).clone();
+    renderer.render(View::FoundNode {
+        name: name.clone(),
+        node_id: node.id.clone(),
+    });
+
+    let mut names_to_bounds: HashMap<String, Rectangle> = HashMap::new();
+    if let Some(bounds) = node.render_bounds() {
+        names_to_bounds.insert(name.clone(), bounds.clone());
+    }
+    let mut names_to_colors: HashMap<String, Color> = HashMap::new();
+    if let Some(color) = node.solid_color() {
+        names_to_colors.insert(name.clone(), color);
+    }
+    Ok(FetcherEntry {
+        app_config,
+        from_cache: false,
+        image_names_to_ids: HashMap::from([(name.clone(), node.id.clone())]),
+        image_names_to_files: HashMap::from([(name, node_url.file_id.clone())]),
+        image_names_to_bounds: names_to_bounds,
+        image_names_to_colors: names_to_colors,
+        image_names_to_res_names: HashMap::new(),
+        file_versions: HashMap::from([(node_url.file_id, file_version)]),
+        config_hash,
+        node_url: Some(url.to_string()),
+        target: None,
+    })
+}
+
+/// Read and validate the config. Returns it with the hash of the config file content.
+pub fn read_config(
+    yaml_config_path: &String,
+    renderer: &Renderer,
+) -> Result<(AppConfig, String), AppError> {
+    read_config_with(yaml_config_path, renderer, validate_app_config)
+}
+
+/// Read the config of `fxa export`. Targets may have their own `mainRes`, so it is checked
+/// for every target with [validate_target_config] instead.
+pub fn read_targets_config(
+    yaml_config_path: &String,
+    renderer: &Renderer,
+) -> Result<(AppConfig, String), AppError> {
+    read_config_with(yaml_config_path, renderer, validate_export_settings)
+}
+
+fn read_config_with(
+    yaml_config_path: &String,
+    renderer: &Renderer,
+    validate: fn(&AppConfig, &String) -> Result<(), AppError>,
+) -> Result<(AppConfig, String), AppError> {
     renderer.new_line();
     renderer.render(View::ReadingConfig {
         path: yaml_config_path.clone(),
     });
     let app_config = AppConfig::from_file(yaml_config_path)?;
-    validate_app_config(&app_config, &yaml_config_path)?;
+    let config_hash = fs::read(yaml_config_path)
+        .map(|content| content_hash(&content))
+        .map_err(|e| AppError::AppConfigOpen(e.to_string()))?;
+    validate(&app_config, yaml_config_path)?;
     renderer.render(View::ReceivedConfig {
         path: yaml_config_path.clone(),
     });
+    Ok((app_config, config_hash))
+}
 
-    let document_url = format!("{}{}", FIGMA_FILES_ENDPOINT, &app_config.figma.file_id);
-    renderer.render(View::FetchingDom {
-        url: document_url.clone(),
-    });
-    let (document, from_cache) = fetch_dom(&api, &app_config)?;
-    renderer.render(View::DomFetched {
-        url: document_url.clone(),
-        from_cache,
-    });
-
-    renderer.render(View::ProcessingDom);
-    let desired_frame_name = match fetcher_target {
-        FetcherTarget::Images => &app_config.common.images.figma_frame_name,
-        FetcherTarget::Icons => &app_config.common.icons.figma_frame_name,
-    };
-    let names_to_ids = find_images_frame(&document, &app_config, desired_frame_name)?;
-
-    renderer.render(View::FoundImages {
-        frame_name: desired_frame_name.clone(),
-    });
-    Ok(FetcherEntry {
-        app_config,
-        document,
-        from_cache,
-        image_names_to_ids: names_to_ids,
-    })
+/// Load the document of Figma file. The cached document is used only if the file hasn't changed
+/// since it was cached, which is checked with a cheap request of the file version.
+fn fetch_dom(api: &FigmaApi, file_id: &String) -> Result<(FigmaGetFileResponse, bool), AppError> {
+    let (file, from_cache) = api.get_document(file_id)?;
+    if from_cache && is_cache_stale(&file.version, &api.get_file_version(file_id)?) {
+        api.invalidate_document(file_id)?;
+        return api.get_document(file_id);
+    }
+    Ok((file, from_cache))
 }
 
-fn fetch_dom(api: &FigmaApi, app_config: &AppConfig) -> Result<(Document, bool), AppError> {
-    let file_id = &app_config.figma.file_id;
-    api.get_document(&file_id)
+/// Returns `true` if the cached document is not of the current version of the file.
+/// Documents cached by older versions of the app have no version, so they are always stale.
+fn is_cache_stale(cached_version: &Option<String>, current_version: &str) -> bool {
+    cached_version.as_deref() != Some(current_version)
 }
 
-fn find_images_frame(
-    document: &Document,
-    app_config: &AppConfig,
+/// Types of the nodes which can contain the frame with resources, or be such a frame
+const CONTAINER_TYPES: [&str; 4] = ["FRAME", "SECTION", "GROUP", "COMPONENT_SET"];
+
+/// Find the frame with resources on all pages, or on the page from the config.
+///
+/// Frames are searched recursively inside sections, groups and other frames. The desired frame
+/// name can be a path like `Page/Section/Icons`, where each name is the page or one
+/// of the parents of the frame, to choose one of the frames with the same name.
+///
+/// The shallowest frame is taken, and frames with the same name at the same depth are ambiguous,
+/// also when they are placed right on pages.
+fn find_images_frame<'a>(
+    document: &'a Document,
+    page_name: &Option<String>,
     desired_frame_name: &String,
-) -> Result<HashMap<String, String>, AppError> {
-    let frame = document
-        .children
-        .iter()
-        .filter(|&canvas| {
-            if let Some(desired_page_name) = &app_config.figma.page_name {
-                desired_page_name == &canvas.name
+) -> Result<&'a Frame, AppError> {
+    let mut frames: Vec<(Vec<&str>, &Frame)> = Vec::new();
+    for canvas in document.children.iter().filter(|&canvas| {
+        if let Some(desired_page_name) = page_name {
+            desired_page_name == &canvas.name
+        } else {
+            true
+        }
+    }) {
+        for frame in &canvas.children {
+            // Documents cached by older versions of the app have no types, only pages were searched
+            if frame.node_type.is_empty() {
+                frames.push((vec![canvas.name.as_str(), frame.name.as_str()], frame));
             } else {
-                true
+                collect_container_frames(frame, vec![canvas.name.as_str()], &mut frames);
             }
-        })
-        .flat_map(|canvas| &canvas.children)
-        .find(|frame| &frame.name == desired_frame_name);
-
-    if let Some(frame) = frame {
-        let names_to_ids = collect_names_to_ids(&frame);
-        if names_to_ids.is_empty() {
-            Err(AppError::DesiredFrameIsEmpty(desired_frame_name.clone()))
-        } else {
-            Ok(names_to_ids)
         }
-    } else {
-        Err(AppError::FindDesiredFrame(desired_frame_name.clone()))
+    }
+
+    let mut found: Vec<&(Vec<&str>, &Frame)> = frames
+        .iter()
+        .filter(|(path, _)| matches_path(desired_frame_name, path))
+        .collect();
+    if let Some(depth) = found.iter().map(|(path, _)| path.len()).min() {
+        found.retain(|(path, _)| path.len() == depth);
+    }
+    match found.as_slice() {
+        [] => Err(AppError::FindDesiredFrame(desired_frame_name.clone())),
+        [(_, frame)] => match &frame.children {
+            Some(children) if !children.is_empty() => Ok(frame),
+            _ => Err(AppError::DesiredFrameIsEmpty(desired_frame_name.clone())),
+        },
+        _ => Err(AppError::AmbiguousFrame(
+            desired_frame_name.clone(),
+            found
+                .iter()
+                .map(|(path, frame)| format!("`{}` (node {})", path.join("/"), frame.id))
+                .collect::<Vec<String>>()
+                .join(", "),
+        )),
     }
 }
 
-fn collect_names_to_ids(frame: &Frame) -> HashMap<String, String> {
-    let mut hash_map: HashMap<String, String> = HashMap::new();
+/// Collect the frame and all container nodes inside it with the names of their parents.
+fn collect_container_frames<'a>(
+    frame: &'a Frame,
+    parents: Vec<&'a str>,
+    frames: &mut Vec<(Vec<&'a str>, &'a Frame)>,
+) {
+    if !CONTAINER_TYPES.contains(&frame.node_type.as_str()) {
+        return;
+    }
+    let mut path = parents;
+    path.push(frame.name.as_str());
     if let Some(children) = &frame.children {
-        for frame in children {
-            hash_map.insert(frame.name.clone(), frame.id.clone());
+        for child in children {
+            collect_container_frames(child, path.clone(), frames);
         }
     }
-    hash_map
+    frames.push((path, frame));
+}
+
+/// Returns `true` if the desired name is the name of the frame (the last one in the path),
+/// optionally prefixed with the names of its parents, e.g. `Page/Icons` or `Section/Icons`.
+/// Names themselves may contain slashes, so they are matched as whole strings.
+fn matches_path(desired_name: &str, path: &[&str]) -> bool {
+    match path.split_last() {
+        Some((name, parents)) => {
+            desired_name == *name
+                || desired_name
+                    .strip_suffix(name)
+                    .and_then(|prefix| prefix.strip_suffix('/'))
+                    .is_some_and(|prefix| {
+                        (0..parents.len()).any(|i| matches_path(prefix, &parents[..=i]))
+                    })
+        }
+        None => false,
+    }
+}
+
+/// Link to the node in the Figma file.
+fn figma_node_url(file_id: &str, node_id: &str) -> String {
+    format!(
+        "https://www.figma.com/file/{}?node-id={}",
+        file_id,
+        node_id.replace(':', "%3A"),
+    )
+}
+
+/// Name of the node with its id and link, e.g. `ic/search` (1:2, https://...)
+fn describe_node(file_id: &str, name: &str, node_id: &str) -> String {
+    format!(
+        "`{}` ({}, {})",
+        name,
+        node_id,
+        figma_node_url(file_id, node_id)
+    )
+}
+
+/// Resource name with the list of nodes exported under it, e.g.
+/// `ic_search`: `ic/search` (1:2, https://...), `ic search` (1:3, https://...)
+fn describe_duplicate(file_id: &str, duplicate: &Duplicate) -> String {
+    let nodes: Vec<String> = duplicate
+        .resources
+        .iter()
+        .map(|(name, node_id)| describe_node(file_id, name, node_id))
+        .collect();
+    format!("`{}`: {}", duplicate.res_name, nodes.join(", "))
 }
 
 fn validate_app_config(app_config: &AppConfig, yaml_config_path: &String) -> Result<(), AppError> {
+    validate_export_settings(app_config, yaml_config_path)?;
+
     let common_main_res = app_config.android.main_res.clone();
     let images_main_res = app_config.android.images.main_res.clone();
     let icons_main_res = app_config.android.icons.main_res.clone();
-
     match (common_main_res, images_main_res, icons_main_res) {
         // There are no mainRes
         (None, None, None) => Err(AppError::AppConfigInvalidMainResCommon(
@@ -133,3 +532,274 @@ fn validate_app_config(app_config: &AppConfig, yaml_config_path: &String) -> Res
         _ => Ok(()),
     }
 }
+
+/// Check the config of the target, which needs `mainRes` only for its own resource type.
+pub fn validate_target_config(
+    app_config: &AppConfig,
+    resource_type: &ResourceType,
+    yaml_config_path: &String,
+) -> Result<(), AppError> {
+    let path = yaml_config_path.clone();
+    match resource_type {
+        ResourceType::Images if app_config.main_res_images().is_none() => {
+            Err(AppError::AppConfigInvalidMainResImages(path))
+        }
+        ResourceType::Icons if app_config.main_res_icons().is_none() => {
+            Err(AppError::AppConfigInvalidMainResIcons(path))
+        }
+        ResourceType::Colors if app_config.main_res_colors().is_none() => {
+            Err(AppError::AppConfigInvalidMainResColors(path))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check everything but `mainRes`: routes and templates of the names.
+fn validate_export_settings(
+    app_config: &AppConfig,
+    yaml_config_path: &String,
+) -> Result<(), AppError> {
+    let routes = app_config
+        .android
+        .images
+        .routes
+        .iter()
+        .chain(&app_config.android.icons.routes);
+    for route in routes {
+        if route.prefix.is_none() && route.regex.is_none() {
+            return Err(AppError::AppConfigInvalidRoute(
+                yaml_config_path.clone(),
+                route.main_res.clone(),
+                "there is neither `prefix` nor `regex`".to_string(),
+            ));
+        }
+    }
+
+    for collect in [
+        &app_config.common.images.collect,
+        &app_config.common.icons.collect,
+        &app_config.common.colors.collect,
+    ] {
+        if let Some(template) = &collect.variant_name_template {
+            let invalid_template = |cause: String| {
+                AppError::AppConfigInvalidVariantTemplate(
+                    yaml_config_path.clone(),
+                    template.clone(),
+                    cause,
+                )
+            };
+            if !collect.split_variants {
+                return Err(invalid_template(
+                    "variants are named only with `collect.splitVariants: true`".to_string(),
+                ));
+            }
+            validate_variant_name_template(template).map_err(invalid_template)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_find_frame_in_sections() {
+    let document: Document = serde_json::from_str(
+        r#"{
+            "id": "0:0", "name": "Document",
+            "children": [
+                { "id": "1:0", "name": "Android", "children": [
+                    { "id": "1:1", "name": "Resources", "type": "SECTION", "children": [
+                        { "id": "1:2", "name": "Icons", "type": "FRAME", "children": [
+                            { "id": "1:3", "name": "ic_24/search", "type": "COMPONENT" }
+                        ] }
+                    ] }
+                ] },
+                { "id": "2:0", "name": "iOS", "children": [
+                    { "id": "2:1", "name": "Icons", "type": "FRAME", "children": [
+                        { "id": "2:2", "name": "ic_24/close", "type": "COMPONENT" }
+                    ] },
+                    { "id": "2:3", "name": "Icons", "type": "COMPONENT", "children": [
+                        { "id": "2:4", "name": "Vector", "type": "VECTOR" }
+                    ] }
+                ] },
+                { "id": "3:0", "name": "Web", "children": [
+                    { "id": "3:1", "name": "Light", "type": "SECTION", "children": [
+                        { "id": "3:2", "name": "Icons", "type": "FRAME", "children": [
+                            { "id": "3:3", "name": "Icons", "type": "GROUP", "children": [
+                                { "id": "3:4", "name": "ic_24/menu", "type": "COMPONENT" }
+                            ] }
+                        ] }
+                    ] },
+                    { "id": "3:5", "name": "Dark", "type": "SECTION", "children": [
+                        { "id": "3:6", "name": "Icons", "type": "FRAME", "children": [
+                            { "id": "3:7", "name": "ic_24/menu", "type": "COMPONENT" }
+                        ] }
+                    ] }
+                ] },
+                { "id": "4:0", "name": "Cached", "children": [
+                    { "id": "4:1", "name": "Images", "children": [
+                        { "id": "4:2", "name": "img_logo" }
+                    ] }
+                ] },
+                { "id": "5:0", "name": "Promo", "children": [
+                    { "id": "5:1", "name": "Illustrations", "type": "FRAME", "children": [
+                        { "id": "5:2", "name": "img_sale", "type": "FRAME" }
+                    ] },
+                    { "id": "5:3", "name": "Illustrations", "type": "FRAME", "children": [
+                        { "id": "5:4", "name": "img_gift", "type": "FRAME" }
+                    ] }
+                ] }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let find = |page_name: Option<&str>, name: &str| {
+        find_images_frame(
+            &document,
+            &page_name.map(|p| p.to_string()),
+            &name.to_string(),
+        )
+        .map(|frame| frame.id.clone())
+    };
+
+    assert_eq!(find(Some("Android"), "Icons").unwrap(), "1:2");
+    assert_eq!(find(None, "Android/Icons").unwrap(), "1:2");
+    assert_eq!(find(None, "Android/Resources/Icons").unwrap(), "1:2");
+    assert_eq!(find(None, "Resources/Icons").unwrap(), "1:2");
+    assert_eq!(find(None, "iOS/Icons").unwrap(), "2:1");
+    // The least nested frame is preferred
+    assert_eq!(find(None, "Icons").unwrap(), "2:1");
+    // Frames right on a page with the same name are ambiguous too
+    match find(None, "Illustrations") {
+        Err(AppError::AmbiguousFrame(_, candidates)) => assert_eq!(
+            candidates,
+            "`Promo/Illustrations` (node 5:1), `Promo/Illustrations` (node 5:3)"
+        ),
+        result => panic!("unexpected result {:?}", result),
+    }
+    // The shallowest of the nested frames with the same name
+    assert_eq!(find(None, "Light/Icons").unwrap(), "3:2");
+    assert!(matches!(
+        find(Some("Web"), "Icons"),
+        Err(AppError::AmbiguousFrame(_, _))
+    ));
+    // Nodes of the cached documents without types
+    assert_eq!(find(None, "Images").unwrap(), "4:1");
+    assert!(matches!(
+        find(None, "img_logo"),
+        Err(AppError::FindDesiredFrame(_))
+    ));
+    assert!(matches!(
+        find(None, "iOS/Resources/Icons"),
+        Err(AppError::FindDesiredFrame(_))
+    ));
+}
+
+#[test]
+fn test_is_cache_stale() {
+    let cached_version = Some("1001".to_string());
+    assert!(!is_cache_stale(&cached_version, "1001"));
+    // The file has been changed by a designer after the document was cached
+    assert!(is_cache_stale(&cached_version, "1002"));
+    assert!(is_cache_stale(&None, "1001"));
+}
+
+#[test]
+fn test_is_requested() {
+    let requested = vec!["ic_close".to_string(), "img_logo".to_string()];
+    assert!(is_requested(&"ic_close".to_string(), &requested));
+    assert!(is_requested(&"img_logo_dark".to_string(), &requested));
+    assert!(!is_requested(&"ic_search".to_string(), &requested));
+    assert!(is_requested(&"ic_search".to_string(), &[]));
+}
+
+#[test]
+fn test_validate_target_config() {
+    let path = "fxa.yaml".to_string();
+    let yaml = r#"
+        figma:
+            fileId: PRODUCT
+        android:
+            icons:
+                mainRes: ./core-ui/res
+        targets:
+            - name: payments-illustrations
+              resourceType: images
+              figmaFrameName: Illustrations
+              mainRes: ./feature-payments/res
+            - name: palette
+              resourceType: colors
+              figmaFrameName: Colors
+            - name: core-icons
+              resourceType: icons
+              figmaFrameName: Icons
+        "#;
+    let app_config: AppConfig = serde_yaml::from_str(yaml).unwrap();
+    // Images have no `mainRes` in `android`, but the target has its own
+    assert!(validate_app_config(&app_config, &path).is_err());
+    assert!(validate_export_settings(&app_config, &path).is_ok());
+
+    let validate = |index: usize| {
+        let target = &app_config.targets[index];
+        let target_config = app_config.clone().for_target(target).unwrap();
+        validate_target_config(&target_config, &target.resource_type, &path)
+    };
+    assert!(validate(0).is_ok());
+    assert!(matches!(
+        validate(1),
+        Err(AppError::AppConfigInvalidMainResColors(_))
+    ));
+    assert!(validate(2).is_ok());
+}
+
+#[test]
+fn test_fetch_sources_duplicates() {
+    let api = FigmaApi::from_token(&Some("token".to_string())).unwrap();
+    let file = r#"{
+            "document": { "id": "0:0", "name": "Document", "children": [
+                { "id": "1:0", "name": "Android", "children": [
+                    { "id": "1:1", "name": "Icons", "type": "FRAME", "children": [
+                        { "id": "1:2", "name": "ic_24/search", "type": "COMPONENT" },
+                        { "id": "1:3", "name": "ic_close", "type": "COMPONENT" },
+                        { "id": "1:4", "name": "ic 24 search", "type": "COMPONENT" }
+                    ] }
+                ] }
+            ] }
+        }"#;
+    let fetch = |duplicates: &str, requested_names: &[&str]| {
+        let app_config: AppConfig = serde_yaml::from_str(&format!(
+            "figma:\n  fileId: FILE\ncommon:\n  icons:\n    figmaFrameName: Icons\n    collect:\n      duplicates: {}\nandroid:\n  mainRes: ./res\n",
+            duplicates
+        ))
+        .unwrap();
+        let mut documents = FetchedDocuments::from([(
+            "FILE".to_string(),
+            (serde_json::from_str(file).unwrap(), true),
+        )]);
+        let requested_names: Vec<String> = requested_names.iter().map(|n| n.to_string()).collect();
+        fetch_sources(
+            &api,
+            app_config,
+            String::new(),
+            FetcherTarget::Icons,
+            &requested_names,
+            &mut documents,
+            &Renderer(),
+        )
+    };
+
+    // The requested name collides with the one which is not requested
+    assert!(matches!(
+        fetch("fail", &["ic_24/search"]),
+        Err(AppError::DuplicateResourceNames(_))
+    ));
+    assert!(fetch("fail", &["ic_close"]).is_ok());
+
+    // Only the res name gets the suffix, the duplicate is known by it
+    let entry = fetch("numberSuffix", &[]).unwrap();
+    assert_eq!(entry.image_names_to_ids["ic_24/search"], "1:2");
+    assert_eq!(entry.image_names_to_ids["ic_24_search_2"], "1:4");
+    assert_eq!(
+        entry.image_names_to_res_names,
+        HashMap::from([("ic_24_search_2".to_string(), "ic_24_search_2".to_string())])
+    );
+    assert!(!entry.image_names_to_ids.contains_key("ic 24 search_2"));
+}
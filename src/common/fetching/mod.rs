@@ -1,4 +1,12 @@
+mod collector;
 mod fetcher;
+mod node_url;
 mod view;
 pub use fetcher::fetch;
+pub use fetcher::fetch_node;
+pub use fetcher::fetch_sources;
+pub use fetcher::read_targets_config;
+pub use fetcher::validate_target_config;
+pub use fetcher::FetchedDocuments;
+pub use fetcher::FetcherEntry;
 pub use fetcher::FetcherTarget;
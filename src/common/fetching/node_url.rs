@@ -0,0 +1,83 @@
+use reqwest::Url;
+
+use crate::common::error::AppError;
+
+/// Kinds of Figma links which point to a node of a file.
+const FILE_PATH_KINDS: [&str; 3] = ["file", "design", "proto"];
+
+/// Figma node from the link like `https://www.figma.com/file/KEY/Name?node-id=12-34`.
+#[derive(Debug, PartialEq)]
+pub struct FigmaNodeUrl {
+    pub file_id: String,
+    /// Node id in the form of Figma API, e.g. `12:34`
+    pub node_id: String,
+}
+
+/// Parse the link to the node copied from Figma.
+///
+/// Both `/file/` and `/design/` links are supported, with the node id in both the old
+/// (`12%3A34`) and the new (`12-34`) form. Links to branches point to the branch file.
+pub fn parse_figma_url(url: &str) -> Result<FigmaNodeUrl, AppError> {
+    let invalid = |reason: &str| AppError::InvalidFigmaUrl(url.to_string(), reason.to_string());
+    let parsed = Url::parse(url.trim()).map_err(|e| invalid(&e.to_string()))?;
+    if !parsed
+        .host_str()
+        .is_some_and(|host| host == "figma.com" || host.ends_with(".figma.com"))
+    {
+        return Err(invalid("it is not a Figma link"));
+    }
+
+    let segments: Vec<&str> = parsed
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    let file_id = match segments.as_slice() {
+        [kind, _, "branch", branch_id, ..] if FILE_PATH_KINDS.contains(kind) => branch_id,
+        [kind, file_id, ..] if FILE_PATH_KINDS.contains(kind) => file_id,
+        _ => return Err(invalid("there is no file key")),
+    };
+
+    // Query values are already percent-decoded, so the old form is `12:34` here
+    let node_id = parsed
+        .query_pairs()
+        .find(|(key, _)| key == "node-id")
+        .map(|(_, value)| value.replace('-', ":"))
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| invalid("there is no node id"))?;
+
+    Ok(FigmaNodeUrl {
+        file_id: file_id.to_string(),
+        node_id,
+    })
+}
+
+#[test]
+fn test_parse_figma_url() {
+    let parse =
+        |url: &str| parse_figma_url(url).map(|node| format!("{} {}", node.file_id, node.node_id));
+
+    assert_eq!(
+        parse("https://www.figma.com/file/KEY/Name?node-id=12-34").unwrap(),
+        "KEY 12:34"
+    );
+    assert_eq!(
+        parse("https://www.figma.com/file/KEY/Name?type=design&node-id=12%3A34&mode=dev").unwrap(),
+        "KEY 12:34"
+    );
+    assert_eq!(
+        parse("https://www.figma.com/design/KEY/branch/BRANCH/Name?node-id=1-2&t=abc").unwrap(),
+        "BRANCH 1:2"
+    );
+    assert!(matches!(
+        parse("https://www.figma.com/file/KEY/Name"),
+        Err(AppError::InvalidFigmaUrl(_, _))
+    ));
+    assert!(matches!(
+        parse("https://example.com/file/KEY/Name?node-id=1-2"),
+        Err(AppError::InvalidFigmaUrl(_, _))
+    ));
+    assert!(matches!(
+        parse("not a link"),
+        Err(AppError::InvalidFigmaUrl(_, _))
+    ));
+}
@@ -1,6 +1,7 @@
 use crossterm::style::Stylize;
 
 use crate::common::renderer::{Indentable, Renderable};
+use crate::models::config::DuplicatesRule;
 
 pub enum View {
     ReadingConfig { path: String },
@@ -9,6 +10,9 @@ pub enum View {
     DomFetched { url: String, from_cache: bool },
     ProcessingDom,
     FoundImages { frame_name: String },
+    FetchingNode { url: String },
+    FoundNode { name: String, node_id: String },
+    DuplicatesResolved(String, DuplicatesRule),
 }
 
 impl Renderable for View {
@@ -54,6 +58,26 @@ impl Renderable for View {
                 "Found".indent().bold().green(),
                 &frame_name,
             ),
+            View::FetchingNode { url } => format!(
+                "{} figma node from {}",
+                "Fetching".indent().bold().cyan(),
+                &url,
+            ),
+            View::FoundNode { name, node_id } => format!(
+                "{} figma node `{}` ({})\n",
+                "Found".indent().bold().green(),
+                &name,
+                &node_id,
+            ),
+            View::DuplicatesResolved(description, rule) => format!(
+                "{} same resource name {}, {}\n",
+                "Warning".indent().bold().yellow(),
+                &description,
+                match rule {
+                    DuplicatesRule::NumberSuffix => "numbered suffixes are added",
+                    _ => "only the first one is exported",
+                },
+            ),
         }
     }
 }
@@ -1,4 +1,10 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::common::error::AppError;
 
 pub const TEMP_DIR_PATH: &str = ".fxa";
 
@@ -27,8 +33,331 @@ pub fn create_dir(path: &String) -> Result<(), std::io::Error> {
     fs::create_dir_all(path)
 }
 
-/// Move file from one place to another, replacing the original file if `to` already exists.
-/// This will not work if the new name is on a different mount point.
-pub fn move_file(from: &String, to: &String) -> Result<(), std::io::Error> {
-    fs::rename(from, to)
+/// Write the content to the file, replacing the original file if it already exists.
+///
+/// The content is written to the temporary file next to the destination and then renamed,
+/// so the destination never contains partially written data. The temporary file is located
+/// in the same directory, so the rename never crosses mount points.
+///
+/// # Arguments
+///
+/// * `path` - Path to the destination file
+/// * `content` - New content of the file
+pub fn write_file_atomically(path: &String, content: &[u8]) -> Result<(), std::io::Error> {
+    let path = Path::new(path);
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temporary_path: PathBuf = path.with_file_name(format!(".{}.fxa-tmp", file_name));
+
+    let result = fs::File::create(&temporary_path)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temporary_path, path));
+    if result.is_err() {
+        fs::remove_file(&temporary_path).unwrap_or_default();
+    }
+    result
+}
+
+/// Returns `true` if the file already exists and has the same content.
+///
+/// XML and SVG files are compared by their elements and attributes, so the file is considered
+/// unchanged even if it was reformatted in the IDE.
+///
+/// # Arguments
+///
+/// * `path` - Path to the file in the project
+/// * `content` - New content of the file
+pub fn is_unchanged(path: &String, content: &[u8]) -> bool {
+    unchanged_content(path, content).is_some()
+}
+
+/// Returns the content of the file if it is unchanged in the sense of [is_unchanged].
+/// The file is left as is then, so its own content must be locked instead of the new one.
+pub fn unchanged_content(path: &String, content: &[u8]) -> Option<Vec<u8>> {
+    let old_content = fs::read(path).ok()?;
+    if old_content == content {
+        return Some(old_content);
+    }
+    let is_xml = matches!(
+        Path::new(path).extension().and_then(|e| e.to_str()),
+        Some("xml") | Some("svg")
+    );
+    if !is_xml {
+        return None;
+    }
+    let same_xml = match (
+        std::str::from_utf8(&old_content),
+        std::str::from_utf8(content),
+    ) {
+        (Ok(old_xml), Ok(new_xml)) => match (normalize_xml(old_xml), normalize_xml(new_xml)) {
+            (Some(old_xml), Some(new_xml)) => old_xml == new_xml,
+            _ => false,
+        },
+        _ => false,
+    };
+    if same_xml {
+        Some(old_content)
+    } else {
+        None
+    }
+}
+
+/// Write XML elements with sorted attributes and without whitespaces between elements.
+fn normalize_xml(xml: &str) -> Option<String> {
+    let document = roxmltree::Document::parse(xml).ok()?;
+    let mut normalized = String::new();
+    write_normalized_node(&document.root_element(), &mut normalized);
+    Some(normalized)
+}
+
+fn write_normalized_node(node: &roxmltree::Node, out: &mut String) {
+    if node.is_text() {
+        out.push_str(node.text().unwrap_or_default().trim());
+        return;
+    }
+    if !node.is_element() {
+        return;
+    }
+    let tag_name = node.tag_name();
+    out.push_str(&format!(
+        "<{{{}}}{}",
+        tag_name.namespace().unwrap_or_default(),
+        tag_name.name()
+    ));
+    let mut attributes = node
+        .attributes()
+        .iter()
+        .map(|a| {
+            format!(
+                " {{{}}}{}=\"{}\"",
+                a.namespace().unwrap_or_default(),
+                a.name(),
+                a.value()
+            )
+        })
+        .collect::<Vec<String>>();
+    attributes.sort();
+    out.push_str(&attributes.concat());
+    out.push('>');
+    for child in node.children() {
+        write_normalized_node(&child, out);
+    }
+    out.push_str("</>");
+}
+
+/// Files of the export, which are written to the project all at once.
+///
+/// Nothing is written until [StagedFiles::commit] is called. If writing of any file fails,
+/// the files written before are restored, so the project never contains a mix of new and old
+/// resources.
+#[derive(Default)]
+pub struct StagedFiles {
+    files: Vec<(String, Vec<u8>)>,
+    /// Files which are deleted after the staged files are written
+    removed_files: Vec<String>,
+}
+
+/// State of the file in the project before the commit, to restore it on failure.
+enum Backup {
+    Replaced(String, Vec<u8>),
+    Created(String),
+    CreatedDir(PathBuf),
+}
+
+impl StagedFiles {
+    /// Add file to the export.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the file in the project
+    /// * `content` - New content of the file
+    pub fn stage(&mut self, path: String, content: Vec<u8>) {
+        self.files.push((path, content));
+    }
+
+    /// Delete the file from the project with the commit, if it exists.
+    pub fn stage_removal(&mut self, path: String) {
+        if Path::new(&path).is_file() {
+            self.removed_files.push(path);
+        }
+    }
+
+    /// Number of the files which are written, not deleted.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Write all staged files to the project, creating their directories if necessary,
+    /// and delete the files staged for removal. In case of failure, all changes are rolled back.
+    pub fn commit(self) -> Result<(), AppError> {
+        let mut backups: Vec<Backup> = Vec::new();
+        let changes = self
+            .files
+            .iter()
+            .map(|(path, content)| (path, Some(content)))
+            .chain(self.removed_files.iter().map(|path| (path, None)));
+        for (path, content) in changes {
+            let result = match content {
+                Some(content) => write_with_backup(path, content, &mut backups),
+                None => remove_with_backup(path, &mut backups),
+            };
+            if let Err(e) = result {
+                let cause = format!("{}: {}", path, e);
+                return match rollback(backups) {
+                    Ok(()) => Err(AppError::CannotCommitExport(cause)),
+                    Err(e) => Err(AppError::CannotRollbackExport(cause, e.to_string())),
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_with_backup(
+    path: &String,
+    content: &[u8],
+    backups: &mut Vec<Backup>,
+) -> Result<(), std::io::Error> {
+    // Remember every directory we create to remove them on rollback
+    if let Some(dir) = Path::new(path).parent() {
+        let missing_dirs = dir
+            .ancestors()
+            .take_while(|d| !d.as_os_str().is_empty() && !d.exists())
+            .map(|d| d.to_path_buf())
+            .collect::<Vec<PathBuf>>();
+        fs::create_dir_all(dir)?;
+        // The deepest directories must be removed first
+        backups.extend(missing_dirs.into_iter().rev().map(Backup::CreatedDir));
+    }
+    let backup = match fs::read(path) {
+        Ok(old_content) => Backup::Replaced(path.clone(), old_content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Backup::Created(path.clone()),
+        Err(e) => return Err(e),
+    };
+    write_file_atomically(path, content)?;
+    backups.push(backup);
+    Ok(())
+}
+
+fn remove_with_backup(path: &String, backups: &mut Vec<Backup>) -> Result<(), std::io::Error> {
+    let old_content = fs::read(path)?;
+    fs::remove_file(path)?;
+    backups.push(Backup::Replaced(path.clone(), old_content));
+    Ok(())
+}
+
+fn rollback(backups: Vec<Backup>) -> Result<(), std::io::Error> {
+    for backup in backups.into_iter().rev() {
+        match backup {
+            Backup::Replaced(path, old_content) => write_file_atomically(&path, &old_content)?,
+            Backup::Created(path) => fs::remove_file(&path)?,
+            Backup::CreatedDir(dir) => fs::remove_dir(&dir)?,
+        }
+    }
+    Ok(())
+}
+
+/// Intermediate files of the export pipeline. They are saved to the temporary dir
+/// only if the user asked for it, to debug the export.
+pub struct DebugFiles {
+    enabled: bool,
+}
+
+impl DebugFiles {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// Save intermediate file to the temporary dir, if debug files are enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - Name of the file inside the temporary dir
+    /// * `content` - Content of the file
+    pub fn save(&self, file_name: &str, content: &[u8]) -> Result<(), AppError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        create_temp_dir().map_err(|_| AppError::CreateTempDir)?;
+        let path = format!("{}/{}", TEMP_DIR_PATH, file_name);
+        fs::write(&path, content)
+            .map_err(|e| AppError::CannotSaveTemporaryFile(path, e.to_string()))
+    }
+}
+
+#[test]
+fn test_write_file_atomically_replaces_content() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    let path = dir.join("img.webp").to_string_lossy().to_string();
+
+    write_file_atomically(&path, b"old").unwrap();
+    write_file_atomically(&path, b"new").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"new");
+    assert_eq!(fs::read_dir(dir).unwrap().count(), 1);
+}
+
+#[test]
+fn test_staged_files_rollback() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    let existing = dir.join("existing.xml").to_string_lossy().to_string();
+    let created = dir
+        .join("drawable/created.xml")
+        .to_string_lossy()
+        .to_string();
+    // Directory can't be replaced with the file, so the last write fails
+    let invalid = dir.join("drawable").to_string_lossy().to_string();
+    fs::write(&existing, b"old").unwrap();
+
+    let mut staged_files = StagedFiles::default();
+    staged_files.stage(existing.clone(), b"new".to_vec());
+    staged_files.stage(created.clone(), b"new".to_vec());
+    staged_files.stage(invalid, b"new".to_vec());
+    assert!(matches!(
+        staged_files.commit(),
+        Err(AppError::CannotCommitExport(_))
+    ));
+    assert_eq!(fs::read(&existing).unwrap(), b"old");
+    assert!(!Path::new(&created).exists());
+    assert!(!dir.join("drawable").exists());
+}
+
+#[test]
+fn test_staged_files_removal() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    let moved = dir.join("moved.xml").to_string_lossy().to_string();
+    let written = dir.join("written.xml").to_string_lossy().to_string();
+    fs::write(&moved, b"old").unwrap();
+
+    let mut staged_files = StagedFiles::default();
+    staged_files.stage(written.clone(), b"new".to_vec());
+    staged_files.stage_removal(moved.clone());
+    // Files which are already gone are skipped
+    staged_files.stage_removal(dir.join("gone.xml").to_string_lossy().to_string());
+    assert_eq!(staged_files.len(), 1);
+    staged_files.commit().unwrap();
+
+    assert!(!Path::new(&moved).exists());
+    assert_eq!(fs::read(&written).unwrap(), b"new");
+}
+
+#[test]
+fn test_reformatted_xml_is_unchanged() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    let path = dir.join("ic_test.xml").to_string_lossy().to_string();
+    fs::write(
+        &path,
+        "<vector xmlns:android=\"http://schemas.android.com/apk/res/android\"\n    android:width=\"24dp\"\n    android:height=\"24dp\">\n\n    <path android:pathData=\"M0,0\" />\n</vector>\n",
+    )
+    .unwrap();
+
+    let reformatted = "<vector xmlns:a=\"http://schemas.android.com/apk/res/android\" a:height=\"24dp\" a:width=\"24dp\"><path a:pathData=\"M0,0\"/></vector>";
+    assert!(is_unchanged(&path, reformatted.as_bytes()));
+    let changed = reformatted.replace("M0,0", "M1,1");
+    assert!(!is_unchanged(&path, changed.as_bytes()));
 }
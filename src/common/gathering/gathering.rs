@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use crate::{
     common::{
@@ -130,6 +130,27 @@ where
     queue
 }
 
+/// Returns names of all resources of the frame, where pairs of `_light` and `_dark` variants
+/// are replaced by one themed name.
+pub fn all_names(names_to_ids: &HashMap<String, String>) -> Vec<String> {
+    names_to_ids
+        .keys()
+        .map(|name| {
+            let base_name = name.without_theme_suffix();
+            let themed = (name.light() || name.dark())
+                && names_to_ids.contains_key(&base_name.with_light_suffix())
+                && names_to_ids.contains_key(&base_name.with_dark_suffix());
+            if themed {
+                base_name
+            } else {
+                name.clone()
+            }
+        })
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
 fn for_each_scale<C, G, I>(
     app_config: &AppConfig,
     single_scale_format: bool,
@@ -150,3 +171,20 @@ fn for_each_scale<C, G, I>(
         }
     }
 }
+
+#[test]
+fn test_all_names() {
+    let names_to_ids: HashMap<String, String> = [
+        ("img_card", "1:1"),
+        ("img_wallet_light", "1:2"),
+        ("img_wallet_dark", "1:3"),
+        ("img_banner_light", "1:4"),
+    ]
+    .iter()
+    .map(|(name, id)| (name.to_string(), id.to_string()))
+    .collect();
+    assert_eq!(
+        all_names(&names_to_ids),
+        vec!["img_banner_light", "img_card", "img_wallet"]
+    );
+}
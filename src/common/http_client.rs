@@ -1,21 +1,35 @@
 use std::time::Duration;
 
 use reqwest::blocking::Client;
+use reqwest::header::HeaderValue;
+
+use crate::common::error::AppError;
+use crate::models::credentials::FigmaToken;
 
 /// Create blocking http client to make requests to Figma API.
 ///
-/// `X-FIGMA-TOKEN` header with a personal access token
-/// will be added to each request through this client.
+/// `X-FIGMA-TOKEN` header with a personal access token, or `Authorization: Bearer` header
+/// with OAuth access token will be added to each request through this client.
 ///
 /// # Arguments
 ///
-/// * `token` - Figma personal access token. More details: https://www.figma.com/developers/api#authentication
-pub fn create_http_client(token: &String) -> Client {
+/// * `token` - Figma token. More details: https://www.figma.com/developers/api#authentication
+pub fn create_http_client(token: &FigmaToken) -> Result<Client, AppError> {
+    let (header_name, header_value) = match token {
+        FigmaToken::Personal(token) => ("X-FIGMA-TOKEN", token.trim().to_string()),
+        FigmaToken::OAuth(credentials) => (
+            "Authorization",
+            format!("Bearer {}", credentials.access_token.trim()),
+        ),
+    };
     let mut auth_headers = reqwest::header::HeaderMap::new();
-    auth_headers.insert("X-FIGMA-TOKEN", token.parse().unwrap());
+    let mut auth_header =
+        HeaderValue::from_str(&header_value).map_err(|_| AppError::InvalidToken)?;
+    auth_header.set_sensitive(true);
+    auth_headers.insert(header_name, auth_header);
     reqwest::blocking::Client::builder()
         .timeout(Some(Duration::new(15, 0)))
         .default_headers(auth_headers)
         .build()
-        .unwrap()
+        .map_err(|e| AppError::CannotCreateHttpClient(e.to_string()))
 }
@@ -3,9 +3,11 @@ pub mod fetching;
 pub mod fileutils;
 pub mod gathering;
 pub mod http_client;
+pub mod raster;
 pub mod renderer;
 pub mod res_name;
 pub mod suffixes;
 pub mod suggestions;
+pub mod transliteration;
 pub mod vdtool;
 pub mod webp;
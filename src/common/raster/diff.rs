@@ -0,0 +1,53 @@
+use image::{Rgba, RgbaImage};
+
+/// Maximum difference of a color channel, at which the pixels are still considered equal.
+/// It hides antialiasing differences between renders.
+const CHANNEL_TOLERANCE: u8 = 32;
+
+/// Result of the pixel comparison of two images with the same size.
+pub struct ImageDiff {
+    /// Percentage of the mismatched pixels, from 0 to 100.
+    pub difference: f32,
+    /// Expected image in grayscale with mismatched pixels highlighted in red.
+    pub image: RgbaImage,
+}
+
+/// Compare two images pixel by pixel. Returns `None` if the images have different sizes.
+///
+/// # Arguments
+///
+/// * `expected` - Reference image, e.g. rendered from the original SVG
+/// * `actual` - Image to be checked, e.g. rendered from the converted VectorDrawable
+pub fn compare_images(expected: &RgbaImage, actual: &RgbaImage) -> Option<ImageDiff> {
+    if expected.dimensions() != actual.dimensions() {
+        return None;
+    }
+    let (width, height) = expected.dimensions();
+    let mut image = RgbaImage::new(width, height);
+    let mut mismatched = 0u64;
+
+    for (x, y, expected_pixel) in expected.enumerate_pixels() {
+        let actual_pixel = actual.get_pixel(x, y);
+        let is_mismatched = expected_pixel
+            .0
+            .iter()
+            .zip(actual_pixel.0.iter())
+            .any(|(e, a)| e.abs_diff(*a) > CHANNEL_TOLERANCE);
+        let diff_pixel = if is_mismatched {
+            mismatched += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            // Faded grayscale version of the expected image helps to find mismatches location
+            let [r, g, b, a] = expected_pixel.0;
+            let luma = ((r as u32 * 3 + g as u32 * 6 + b as u32) / 10) as u8;
+            Rgba([luma, luma, luma, a / 4])
+        };
+        image.put_pixel(x, y, diff_pixel);
+    }
+
+    let total = (width as u64 * height as u64).max(1);
+    Some(ImageDiff {
+        difference: mismatched as f32 * 100f32 / total as f32,
+        image,
+    })
+}
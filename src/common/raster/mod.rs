@@ -0,0 +1,166 @@
+pub mod diff;
+
+use std::io::BufWriter;
+
+use image::RgbaImage;
+use tiny_skia::{Pixmap, Transform};
+use usvg::{FitTo, Options, Tree};
+
+use crate::common::vdtool::drawable::parser::parse_vector_drawable;
+use crate::common::vdtool::drawable::svg::write_svg;
+
+/// Parse SVG and render it into the image of the given size.
+/// Returns a description of the problem in case of failure.
+pub fn render_svg(svg_content: &str, width: u32, height: u32) -> Result<RgbaImage, String> {
+    let tree =
+        Tree::from_str(svg_content, &Options::default().to_ref()).map_err(|e| e.to_string())?;
+    render_tree(&tree, width, height)
+}
+
+/// Parse SVG and render it into the image with the size of SVG multiplied by `scale`.
+pub fn render_svg_scaled(svg_content: &str, scale: f32) -> Result<RgbaImage, String> {
+    let tree =
+        Tree::from_str(svg_content, &Options::default().to_ref()).map_err(|e| e.to_string())?;
+    let size = tree.svg_node().size;
+    let width = (size.width() * scale as f64).round() as u32;
+    let height = (size.height() * scale as f64).round() as u32;
+    render_tree(&tree, width, height)
+}
+
+/// Parse SVG and return names of its features which can't be rendered locally.
+///
+/// Fonts are not loaded, so text which is not outlined by Figma would be lost.
+pub fn unsupported_svg_features(svg_content: &str) -> Result<Vec<String>, String> {
+    Tree::from_str(svg_content, &Options::default().to_ref()).map_err(|e| e.to_string())?;
+    let document = roxmltree::Document::parse(svg_content).map_err(|e| e.to_string())?;
+    let has_text = document.descendants().any(|node| node.has_tag_name("text"));
+    Ok(if has_text {
+        vec!["text".to_string()]
+    } else {
+        vec![]
+    })
+}
+
+/// Render the tree with resvg, stretching the image to the given size.
+fn render_tree(tree: &Tree, width: u32, height: u32) -> Result<RgbaImage, String> {
+    let cannot_render = || format!("can't render image with size {}x{}", width, height);
+    let mut pixmap = Pixmap::new(width, height).ok_or_else(cannot_render)?;
+    let size = tree.svg_node().size;
+    let transform = Transform::from_scale(
+        width as f32 / size.width() as f32,
+        height as f32 / size.height() as f32,
+    );
+    resvg::render(tree, FitTo::Original, transform, pixmap.as_mut()).ok_or_else(cannot_render)?;
+    Ok(to_rgba_image(&pixmap))
+}
+
+/// Parse Android VectorDrawable XML and render it into the image of the given size.
+/// VectorDrawable is rendered through its SVG representation, so it shares
+/// the renderer with [render_svg] and the results are comparable.
+pub fn render_vector_drawable(
+    xml_content: &str,
+    width: u32,
+    height: u32,
+) -> Result<RgbaImage, String> {
+    let drawable = parse_vector_drawable(xml_content)?;
+    let mut writer = BufWriter::new(Vec::new());
+    write_svg(&mut writer, &drawable).map_err(|e| e.to_string())?;
+    let svg_content = writer.into_inner().map_err(|e| e.to_string())?;
+    render_svg(&String::from_utf8_lossy(&svg_content), width, height)
+}
+
+/// Convert premultiplied pixmap into the ordinary RGBA image.
+pub fn to_rgba_image(pixmap: &Pixmap) -> RgbaImage {
+    let mut image = RgbaImage::new(pixmap.width(), pixmap.height());
+    for (pixel, color) in image.pixels_mut().zip(pixmap.pixels()) {
+        let color = color.demultiply();
+        pixel.0 = [color.red(), color.green(), color.blue(), color.alpha()];
+    }
+    image
+}
+
+#[test]
+fn test_vector_drawable_renders_like_svg() {
+    let svg_content = r##"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24" viewBox="0 0 24 24">
+        <g transform="translate(12,12) rotate(45) translate(-12,-12)">
+            <path fill="#FF0000" d="M6,6L18,6L18,18L6,18Z" />
+        </g>
+    </svg>"##;
+    let xml_content = r##"<vector xmlns:android="http://schemas.android.com/apk/res/android"
+        android:width="24dp" android:height="24dp"
+        android:viewportWidth="24" android:viewportHeight="24">
+        <group android:rotation="45" android:pivotX="12" android:pivotY="12">
+            <path android:fillColor="#FF0000" android:pathData="M6,6L18,6L18,18L6,18Z" />
+        </group>
+    </vector>"##;
+    let expected = render_svg_scaled(svg_content, 2f32).unwrap();
+    let actual = render_vector_drawable(xml_content, 48, 48).unwrap();
+    let diff = diff::compare_images(&expected, &actual).unwrap();
+    assert_eq!(diff.difference, 0f32);
+
+    // Dropped group transformation must be noticed
+    let xml_content = xml_content.replace("android:rotation=\"45\"", "");
+    let actual = render_vector_drawable(&xml_content, 48, 48).unwrap();
+    let diff = diff::compare_images(&expected, &actual).unwrap();
+    assert!(diff.difference > 1f32);
+}
+
+#[test]
+fn test_render_svg_scaled() {
+    let svg_content = r##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10" viewBox="0 0 20 10">
+        <path fill="#00FF00" d="M0,0L10,0L10,10L0,10Z" />
+    </svg>"##;
+    let image = render_svg_scaled(svg_content, 1.5f32).unwrap();
+    assert_eq!((image.width(), image.height()), (30, 15));
+    assert_eq!(image.get_pixel(5, 7).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(25, 7).0[3], 0);
+}
+
+#[test]
+fn test_render_clip_path_under_transform() {
+    // The clip path is in the units of the clipped element's bounding box, under the group's transform
+    let svg_content = r##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20" viewBox="0 0 20 20">
+        <clipPath id="left-half" clipPathUnits="objectBoundingBox">
+            <rect x="0" y="0" width="0.5" height="1" />
+        </clipPath>
+        <g transform="translate(10,0)">
+            <rect clip-path="url(#left-half)" x="0" y="0" width="10" height="20" fill="#FF0000" />
+        </g>
+    </svg>"##;
+    let image = render_svg(svg_content, 20, 20).unwrap();
+    assert_eq!(image.get_pixel(12, 10).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(17, 10).0[3], 0);
+    assert_eq!(image.get_pixel(5, 10).0[3], 0);
+
+    // Clip path with its own transform inside a scaled group
+    let svg_content = r##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20" viewBox="0 0 20 20">
+        <clipPath id="top" transform="translate(0,-5)">
+            <rect x="0" y="5" width="10" height="5" />
+        </clipPath>
+        <g transform="scale(2)">
+            <g clip-path="url(#top)">
+                <rect x="0" y="0" width="10" height="10" fill="#0000FF" />
+            </g>
+        </g>
+    </svg>"##;
+    let image = render_svg(svg_content, 20, 20).unwrap();
+    assert_eq!(image.get_pixel(10, 5).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(10, 15).0[3], 0);
+}
+
+#[test]
+fn test_unsupported_svg_features() {
+    let supported = r##"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24">
+        <filter id="f"><feGaussianBlur stdDeviation="2" /></filter>
+        <mask id="m"><path fill="#FFFFFF" d="M0,0L12,0L12,12Z" /></mask>
+        <g filter="url(#f)"><path d="M0,0L24,0L24,24Z" /></g>
+        <g mask="url(#m)"><path d="M0,0L24,0L24,24Z" /></g>
+    </svg>"##;
+    assert!(unsupported_svg_features(supported).unwrap().is_empty());
+
+    let unsupported = r##"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24">
+        <text x="0" y="12">Sale</text>
+    </svg>"##;
+    assert_eq!(unsupported_svg_features(unsupported).unwrap(), vec!["text"]);
+    assert!(unsupported_svg_features("<svg").is_err());
+}
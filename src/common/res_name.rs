@@ -1,3 +1,66 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::common::error::AppError;
+use crate::common::transliteration::{has_non_latin_letters, is_transliterable, transliterate};
+use crate::models::config::NamingConfig;
+
+/// Reserved words of Java, which can't be the names of fields of `R` class.
+const JAVA_KEYWORDS: [&str; 53] = [
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "void",
+    "volatile",
+    "while",
+];
+
 /// Cast the name to a valid form for use as a resource in an android app.
 /// The function turns any name into snake_case.
 ///
@@ -40,6 +103,157 @@ pub fn to_res_name(name: &String) -> String {
     output
 }
 
+/// Cast the name to a resource name by the naming rules from the config.
+/// Names from the rename map are used as is.
+pub fn to_res_name_with(name: &String, naming: &NamingConfig) -> String {
+    if let Some(res_name) = naming.rename.get(name) {
+        return res_name.clone();
+    }
+
+    let segments: Vec<&str> = name.split('/').collect();
+    let mut name = segments[naming.strip_path_segments.min(segments.len() - 1)..].join("/");
+
+    // Longer substrings go first, so `%%` is replaced before `%`
+    let mut replacements: Vec<(&String, &String)> = naming.replace.iter().collect();
+    replacements.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then(a.cmp(b)));
+    for (from, to) in replacements {
+        name = name.replace(from, to);
+    }
+    if naming.transliterate {
+        name = transliterate(&name);
+    }
+
+    let mut res_name = to_res_name(&name);
+    if naming.collapse_underscores {
+        while res_name.contains("__") {
+            res_name = res_name.replace("__", "_");
+        }
+    }
+    if naming.trim {
+        res_name = res_name.trim_matches('_').to_string();
+    }
+    if !res_name.starts_with(&naming.prefix) {
+        res_name = format!("{}{}", naming.prefix, res_name);
+    }
+    if !res_name.ends_with(&naming.suffix) {
+        res_name = format!("{}{}", res_name, naming.suffix);
+    }
+    res_name
+}
+
+/// Res name given by the fetcher to the name, e.g. to a duplicate with a number suffix,
+/// or the one made by the naming rules from the config.
+pub fn given_res_name(
+    name: &String,
+    given_res_names: &HashMap<String, String>,
+    naming: &NamingConfig,
+) -> String {
+    given_res_names
+        .get(name)
+        .cloned()
+        .unwrap_or_else(|| to_res_name_with(name, naming))
+}
+
+/// Check that the resource name can be used in an android app, following the rules of aapt:
+/// only lowercase Latin letters, digits and underscores, and the name must be a valid
+/// Java identifier. The error contains the suggested fix.
+///
+/// * `name` - Figma name of the resource
+/// * `res_name` - resource name made from it by the naming rules
+pub fn validate_res_name(
+    name: &str,
+    res_name: &str,
+    naming: &NamingConfig,
+) -> Result<(), AppError> {
+    let renamed = naming.rename.contains_key(name);
+    let (reason, suggestion) = if !renamed && !naming.transliterate && has_non_latin_letters(name) {
+        let mut transliterating = naming.clone();
+        transliterating.transliterate = true;
+        let suggestion = if is_transliterable(name) {
+            format!(
+                "Set `naming.transliterate: true` in the config to export it as `{}`",
+                to_res_name_with(&name.to_string(), &transliterating)
+            )
+        } else {
+            rename_suggestion(name, None)
+        };
+        (
+            "non-Latin letters are replaced with underscores",
+            suggestion,
+        )
+    } else if !res_name.chars().any(|ch| ch.is_ascii_lowercase()) {
+        ("it has no Latin letters", rename_suggestion(name, None))
+    } else if res_name.starts_with(|ch: char| ch.is_ascii_digit()) {
+        // `1_banner` is suggested to be `banner_1`
+        let digits: String = res_name
+            .chars()
+            .take_while(|ch| ch.is_ascii_digit())
+            .collect();
+        let rest = res_name[digits.len()..].trim_matches('_');
+        (
+            "it starts with a digit",
+            rename_suggestion(name, Some(format!("{}_{}", rest, digits))),
+        )
+    } else if JAVA_KEYWORDS.contains(&res_name) {
+        (
+            "it is a Java keyword",
+            rename_suggestion(name, Some(format!("{}_drawable", res_name))),
+        )
+    } else if !res_name
+        .chars()
+        .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+    {
+        (
+            "only lowercase Latin letters, digits and underscores are allowed",
+            rename_suggestion(name, Some(to_res_name(&res_name.to_string()))),
+        )
+    } else {
+        return Ok(());
+    };
+    Err(AppError::InvalidResName(
+        name.to_string(),
+        res_name.to_string(),
+        reason.to_string(),
+        suggestion,
+    ))
+}
+
+/// Check the resource names of all resources for export, by pairs of names given by the user
+/// and resource names. Invalid names would fail only in the Android build, so the export
+/// should stop before anything is downloaded or written.
+///
+/// Each invalid name is passed to `report_error` once, sorted by the names. The result is
+/// `AppError::InvalidResNames` with the count of the invalid names.
+pub fn validate_res_names<'a>(
+    names: impl IntoIterator<Item = (&'a String, &'a String)>,
+    naming: &NamingConfig,
+    mut report_error: impl FnMut(&AppError),
+) -> Result<(), AppError> {
+    let invalid_names: BTreeMap<&String, AppError> = names
+        .into_iter()
+        .filter_map(|(name, res_name)| {
+            validate_res_name(name, res_name, naming)
+                .err()
+                .map(|error| (name, error))
+        })
+        .collect();
+    if invalid_names.is_empty() {
+        return Ok(());
+    }
+    invalid_names.values().for_each(&mut report_error);
+    Err(AppError::InvalidResNames(invalid_names.len()))
+}
+
+fn rename_suggestion(name: &str, suggested_name: Option<String>) -> String {
+    match suggested_name {
+        Some(suggested_name) => format!(
+            "Rename it in Figma, or add `\"{}\": \"{}\"` to `naming.rename` in the config",
+            name, suggested_name
+        ),
+        None => "Rename it in Figma, or add it to `naming.rename` in the config".to_string(),
+    }
+}
+
 #[test]
 fn test_camel_case_to_shake_case() {
     let source_name = "ImgAndroidBanner".to_string();
@@ -74,3 +288,96 @@ fn test_nothing_changes() {
     let res_name = "img_promo_banner".to_string();
     assert_eq!(to_res_name(&source_name), res_name);
 }
+
+#[test]
+fn test_naming_pipeline() {
+    let naming: NamingConfig = serde_yaml::from_str(
+        r#"
+        prefix: "ic_"
+        stripPathSegments: 1
+        replace:
+            "%": "percent"
+            "+": "_plus_"
+        collapseUnderscores: true
+        trim: true
+        rename:
+            "ic_24/Old Name": "ic_renamed"
+        "#,
+    )
+    .unwrap();
+    let res_name = |name: &str| to_res_name_with(&name.to_string(), &naming);
+
+    assert_eq!(res_name("ic_24/discount 100%"), "ic_discount_100percent");
+    assert_eq!(res_name("ic_24/ic_zoom+"), "ic_zoom_plus");
+    assert_eq!(res_name("search"), "ic_search");
+    assert_eq!(res_name("ic_24/Old Name"), "ic_renamed");
+    assert_eq!(res_name("a/b/Close__Button_"), "ic_b_close_button");
+}
+
+#[test]
+fn test_validate_res_name() {
+    let naming = NamingConfig::default();
+    let validate = |name: &str| {
+        let res_name = to_res_name_with(&name.to_string(), &naming);
+        match validate_res_name(name, &res_name, &naming) {
+            Ok(()) => None,
+            Err(AppError::InvalidResName(_, _, reason, suggestion)) => {
+                Some(format!("{}. {}", reason, suggestion))
+            }
+            Err(e) => panic!("Unexpected error {}", e),
+        }
+    };
+
+    assert_eq!(validate("ImgBanner"), None);
+    assert_eq!(
+        validate("1_banner").unwrap(),
+        "it starts with a digit. Rename it in Figma, or add `\"1_banner\": \"banner_1\"` to `naming.rename` in the config"
+    );
+    assert_eq!(
+        validate("new").unwrap(),
+        "it is a Java keyword. Rename it in Figma, or add `\"new\": \"new_drawable\"` to `naming.rename` in the config"
+    );
+    assert_eq!(
+        validate("Баннер").unwrap(),
+        "non-Latin letters are replaced with underscores. Set `naming.transliterate: true` in the config to export it as `banner`"
+    );
+    assert_eq!(
+        validate("ic_café").unwrap(),
+        "non-Latin letters are replaced with underscores. Set `naming.transliterate: true` in the config to export it as `ic_cafe`"
+    );
+    assert_eq!(
+        validate("ic_αβ").unwrap(),
+        "non-Latin letters are replaced with underscores. Rename it in Figma, or add it to `naming.rename` in the config"
+    );
+    assert_eq!(
+        validate("100%").unwrap(),
+        "it has no Latin letters. Rename it in Figma, or add it to `naming.rename` in the config"
+    );
+    assert!(validate_res_name("Banner", "Banner", &naming).is_err());
+}
+
+#[test]
+fn test_validate_res_names() {
+    let naming = NamingConfig::default();
+    let names: Vec<(String, String)> = ["new", "ic_close", "1_banner", "new"]
+        .iter()
+        .map(|name| {
+            (
+                name.to_string(),
+                to_res_name_with(&name.to_string(), &naming),
+            )
+        })
+        .collect();
+    let mut reported: Vec<String> = Vec::new();
+    let result = validate_res_names(
+        names.iter().map(|(name, res_name)| (name, res_name)),
+        &naming,
+        |error| match error {
+            AppError::InvalidResName(name, _, _, _) => reported.push(name.clone()),
+            error => panic!("unexpected error {:?}", error),
+        },
+    );
+    assert!(matches!(result, Err(AppError::InvalidResNames(2))));
+    assert_eq!(reported, vec!["1_banner", "new"]);
+    assert!(validate_res_names(names[1..2].iter().map(|(n, r)| (n, r)), &naming, |_| {}).is_ok());
+}
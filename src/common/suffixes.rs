@@ -6,6 +6,7 @@ pub trait SuffixExt {
     fn dark(&self) -> bool;
     fn with_light_suffix(&self) -> Self;
     fn with_dark_suffix(&self) -> Self;
+    fn without_theme_suffix(&self) -> Self;
 }
 
 impl SuffixExt for String {
@@ -26,4 +27,12 @@ impl SuffixExt for String {
     fn with_dark_suffix(&self) -> Self {
         format!("{}{}", self, SUFFIX_DARK)
     }
+
+    // Returns the string without `_light` or `_dark` suffix.
+    fn without_theme_suffix(&self) -> Self {
+        self.strip_suffix(SUFFIX_LIGHT)
+            .or_else(|| self.strip_suffix(SUFFIX_DARK))
+            .unwrap_or(self)
+            .to_string()
+    }
 }
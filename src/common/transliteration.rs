@@ -0,0 +1,181 @@
+/// Latin spelling of Cyrillic letters of Russian, Ukrainian and Belarusian alphabets.
+const CYRILLIC_TO_LATIN: [(char, &str); 38] = [
+    ('а', "a"),
+    ('б', "b"),
+    ('в', "v"),
+    ('г', "g"),
+    ('д', "d"),
+    ('е', "e"),
+    ('ё', "e"),
+    ('ж', "zh"),
+    ('з', "z"),
+    ('и', "i"),
+    ('й', "y"),
+    ('к', "k"),
+    ('л', "l"),
+    ('м', "m"),
+    ('н', "n"),
+    ('о', "o"),
+    ('п', "p"),
+    ('р', "r"),
+    ('с', "s"),
+    ('т', "t"),
+    ('у', "u"),
+    ('ф', "f"),
+    ('х', "kh"),
+    ('ц', "ts"),
+    ('ч', "ch"),
+    ('ш', "sh"),
+    ('щ', "shch"),
+    ('ъ', ""),
+    ('ы', "y"),
+    ('ь', ""),
+    ('э', "e"),
+    ('ю', "yu"),
+    ('я', "ya"),
+    ('є', "ye"),
+    ('і', "i"),
+    ('ї', "yi"),
+    ('ґ', "g"),
+    ('ў', "u"),
+];
+
+/// Latin letters with diacritics of European alphabets without the marks.
+const DIACRITICS_TO_LATIN: [(char, &str); 73] = [
+    ('à', "a"),
+    ('á', "a"),
+    ('â', "a"),
+    ('ã', "a"),
+    ('ä', "a"),
+    ('å', "a"),
+    ('ā', "a"),
+    ('ă', "a"),
+    ('ą', "a"),
+    ('æ', "ae"),
+    ('ç', "c"),
+    ('ć', "c"),
+    ('č', "c"),
+    ('ď', "d"),
+    ('đ', "d"),
+    ('è', "e"),
+    ('é', "e"),
+    ('ê', "e"),
+    ('ë', "e"),
+    ('ē', "e"),
+    ('ė', "e"),
+    ('ę', "e"),
+    ('ě', "e"),
+    ('ğ', "g"),
+    ('ģ', "g"),
+    ('ì', "i"),
+    ('í', "i"),
+    ('î', "i"),
+    ('ï', "i"),
+    ('ī', "i"),
+    ('į', "i"),
+    ('ı', "i"),
+    ('ķ', "k"),
+    ('ļ', "l"),
+    ('ľ', "l"),
+    ('ł', "l"),
+    ('ñ', "n"),
+    ('ń', "n"),
+    ('ņ', "n"),
+    ('ň', "n"),
+    ('ò', "o"),
+    ('ó', "o"),
+    ('ô', "o"),
+    ('õ', "o"),
+    ('ö', "o"),
+    ('ø', "o"),
+    ('ő', "o"),
+    ('œ', "oe"),
+    ('ŕ', "r"),
+    ('ř', "r"),
+    ('ś', "s"),
+    ('ş', "s"),
+    ('š', "s"),
+    ('ș', "s"),
+    ('ß', "ss"),
+    ('ť', "t"),
+    ('ţ', "t"),
+    ('ț', "t"),
+    ('ù', "u"),
+    ('ú', "u"),
+    ('û', "u"),
+    ('ü', "u"),
+    ('ū', "u"),
+    ('ů', "u"),
+    ('ű', "u"),
+    ('ų', "u"),
+    ('ý', "y"),
+    ('ÿ', "y"),
+    ('ź', "z"),
+    ('ż', "z"),
+    ('ž', "z"),
+    ('ð', "d"),
+    ('þ', "th"),
+];
+
+/// Replace Cyrillic letters of the name with Latin ones, keeping the case of the first letter,
+/// so camelCase names stay camelCase. Diacritics are removed from Latin letters.
+/// Other characters are kept as is.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(transliterate("ИконкаПоиск"), "IkonkaPoisk");
+/// assert_eq!(transliterate("ic_café"), "ic_cafe");
+/// ```
+pub fn transliterate(name: &str) -> String {
+    let mut output = String::new();
+    for ch in name.chars() {
+        let lowercase = ch.to_lowercase().next().unwrap_or(ch);
+        match latin_spelling(lowercase) {
+            Some(latin) if ch.is_uppercase() => {
+                let mut latin = latin.chars();
+                if let Some(first) = latin.next() {
+                    output.push(first.to_ascii_uppercase());
+                    output.extend(latin);
+                }
+            }
+            Some(latin) => output.push_str(latin),
+            None => output.push(ch),
+        }
+    }
+    output
+}
+
+/// Returns `true` if the name has letters which are not Latin, e.g. Cyrillic ones.
+pub fn has_non_latin_letters(name: &str) -> bool {
+    name.chars().any(|ch| ch.is_alphabetic() && !ch.is_ascii())
+}
+
+/// Returns `true` if [transliterate] replaces every non-Latin letter of the name.
+pub fn is_transliterable(name: &str) -> bool {
+    name.chars()
+        .filter(|ch| ch.is_alphabetic() && !ch.is_ascii())
+        .all(|ch| latin_spelling(ch.to_lowercase().next().unwrap_or(ch)).is_some())
+}
+
+fn latin_spelling(lowercase: char) -> Option<&'static str> {
+    CYRILLIC_TO_LATIN
+        .iter()
+        .chain(DIACRITICS_TO_LATIN.iter())
+        .find(|(letter, _)| *letter == lowercase)
+        .map(|(_, latin)| *latin)
+}
+
+#[test]
+fn test_transliterate() {
+    assert_eq!(transliterate("ИконкаПоиск"), "IkonkaPoisk");
+    assert_eq!(
+        transliterate("ic_24/щётка Жёлтая"),
+        "ic_24/shchetka Zheltaya"
+    );
+    assert_eq!(transliterate("ic_search"), "ic_search");
+    assert_eq!(transliterate("Crème_Brûlée"), "Creme_Brulee");
+    assert!(has_non_latin_letters("ic_поиск"));
+    assert!(!has_non_latin_letters("ic_search 100%"));
+    assert!(is_transliterable("ic_поиск_café"));
+    assert!(!is_transliterable("ic_αβ"));
+}
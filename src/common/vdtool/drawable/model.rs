@@ -0,0 +1,176 @@
+/// Android VectorDrawable document, parsed from XML:
+/// ```xml
+/// <vector android:width="24dp" android:height="24dp"
+///         android:viewportWidth="24" android:viewportHeight="24">
+///     <group android:rotation="45" android:pivotX="12" android:pivotY="12">
+///         <clip-path android:pathData="..." />
+///         <path android:pathData="..." android:fillColor="#FF000000" />
+///     </group>
+/// </vector>
+/// ```
+#[derive(Debug, Clone)]
+pub struct VectorDrawable {
+    pub width: f64,
+    pub height: f64,
+    pub viewport_width: f64,
+    pub viewport_height: f64,
+    pub alpha: f64,
+    pub children: Vec<Element>,
+}
+
+/// Child element of `<vector>` or `<group>`.
+#[derive(Debug, Clone)]
+pub enum Element {
+    Group(Group),
+    Path(Path),
+    ClipPath(ClipPath),
+}
+
+/// The `<group>` element with its transformation attributes.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub rotation: f64,
+    pub pivot_x: f64,
+    pub pivot_y: f64,
+    pub scale_x: f64,
+    pub scale_y: f64,
+    pub translate_x: f64,
+    pub translate_y: f64,
+    pub children: Vec<Element>,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self {
+            rotation: 0f64,
+            pivot_x: 0f64,
+            pivot_y: 0f64,
+            scale_x: 1f64,
+            scale_y: 1f64,
+            translate_x: 0f64,
+            translate_y: 0f64,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl Group {
+    /// Returns `true` if the group does not change coordinates of its children.
+    pub fn is_identity(&self) -> bool {
+        self.rotation == 0f64
+            && self.scale_x == 1f64
+            && self.scale_y == 1f64
+            && self.translate_x == 0f64
+            && self.translate_y == 0f64
+    }
+}
+
+/// The `<path>` element.
+#[derive(Debug, Clone)]
+pub struct Path {
+    pub path_data: String,
+    pub fill: Option<Paint>,
+    pub fill_alpha: f64,
+    pub fill_type: FillType,
+    pub stroke: Option<Paint>,
+    pub stroke_width: f64,
+    pub stroke_alpha: f64,
+    pub stroke_line_cap: LineCap,
+    pub stroke_line_join: LineJoin,
+    pub stroke_miter_limit: f64,
+}
+
+/// The `<clip-path>` element. It clips all the following siblings inside the same group.
+#[derive(Debug, Clone)]
+pub struct ClipPath {
+    pub path_data: String,
+}
+
+/// ARGB color, as it is written in VectorDrawable: `#RGB`, `#ARGB`, `#RRGGBB` or `#AARRGGBB`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub alpha: u8,
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    pub fn black() -> Self {
+        Self {
+            alpha: 255,
+            red: 0,
+            green: 0,
+            blue: 0,
+        }
+    }
+
+    /// Opacity of the color from 0.0 to 1.0.
+    pub fn opacity(&self) -> f64 {
+        self.alpha as f64 / 255f64
+    }
+}
+
+/// Fill or stroke of the `<path>`. Gradients are declared with `<aapt:attr>` elements.
+#[derive(Debug, Clone)]
+pub enum Paint {
+    Color(Color),
+    Gradient(Gradient),
+}
+
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub tile_mode: TileMode,
+    pub stops: Vec<GradientStop>,
+}
+
+#[derive(Debug, Clone)]
+pub enum GradientKind {
+    Linear {
+        start_x: f64,
+        start_y: f64,
+        end_x: f64,
+        end_y: f64,
+    },
+    Radial {
+        center_x: f64,
+        center_y: f64,
+        radius: f64,
+    },
+    /// SVG has no sweep gradients, so we don't need its geometry
+    Sweep,
+}
+
+#[derive(Debug, Clone)]
+pub struct GradientStop {
+    pub offset: f64,
+    pub color: Color,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileMode {
+    Clamp,
+    Repeat,
+    Mirror,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FillType {
+    NonZero,
+    EvenOdd,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
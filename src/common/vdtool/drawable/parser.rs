@@ -0,0 +1,293 @@
+use roxmltree::{Document, Node};
+
+use crate::common::vdtool::drawable::model::{
+    ClipPath, Color, Element, FillType, Gradient, GradientKind, GradientStop, Group, LineCap,
+    LineJoin, Paint, Path, TileMode, VectorDrawable,
+};
+
+const ANDROID_NS: &str = "http://schemas.android.com/apk/res/android";
+const AAPT_NS: &str = "http://schemas.android.com/aapt";
+
+/// Parse Android VectorDrawable XML. Returns a description of the problem in case of failure.
+///
+/// # Arguments
+///
+/// * `content` - Content of the VectorDrawable XML file
+pub fn parse_vector_drawable(content: &str) -> Result<VectorDrawable, String> {
+    let document = Document::parse(content).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    if root.tag_name().name() != "vector" {
+        return Err(format!(
+            "root element must be <vector>, but found <{}>",
+            root.tag_name().name()
+        ));
+    }
+
+    let width = parse_dimension(required_attr(&root, "width")?)?;
+    let height = parse_dimension(required_attr(&root, "height")?)?;
+    Ok(VectorDrawable {
+        width,
+        height,
+        viewport_width: parse_number(required_attr(&root, "viewportWidth")?)?,
+        viewport_height: parse_number(required_attr(&root, "viewportHeight")?)?,
+        alpha: number_attr(&root, "alpha", 1f64)?,
+        children: parse_children(&root)?,
+    })
+}
+
+fn parse_children(node: &Node) -> Result<Vec<Element>, String> {
+    let mut children = Vec::new();
+    for child in node.children().filter(|n| n.is_element()) {
+        match child.tag_name().name() {
+            "group" => children.push(Element::Group(parse_group(&child)?)),
+            "path" => children.push(Element::Path(parse_path(&child)?)),
+            "clip-path" => children.push(Element::ClipPath(ClipPath {
+                path_data: required_attr(&child, "pathData")?.to_string(),
+            })),
+            // `<aapt:attr>` elements are handled by their parents
+            _ => (),
+        }
+    }
+    Ok(children)
+}
+
+fn parse_group(node: &Node) -> Result<Group, String> {
+    Ok(Group {
+        rotation: number_attr(node, "rotation", 0f64)?,
+        pivot_x: number_attr(node, "pivotX", 0f64)?,
+        pivot_y: number_attr(node, "pivotY", 0f64)?,
+        scale_x: number_attr(node, "scaleX", 1f64)?,
+        scale_y: number_attr(node, "scaleY", 1f64)?,
+        translate_x: number_attr(node, "translateX", 0f64)?,
+        translate_y: number_attr(node, "translateY", 0f64)?,
+        children: parse_children(node)?,
+    })
+}
+
+fn parse_path(node: &Node) -> Result<Path, String> {
+    let fill = match node.attribute((ANDROID_NS, "fillColor")) {
+        Some(value) => Some(Paint::Color(parse_color(value)?)),
+        None => aapt_gradient(node, "android:fillColor")?.map(Paint::Gradient),
+    };
+    let stroke = match node.attribute((ANDROID_NS, "strokeColor")) {
+        Some(value) => Some(Paint::Color(parse_color(value)?)),
+        None => aapt_gradient(node, "android:strokeColor")?.map(Paint::Gradient),
+    };
+    let fill_type = match node.attribute((ANDROID_NS, "fillType")) {
+        Some("evenOdd") => FillType::EvenOdd,
+        Some("nonZero") | None => FillType::NonZero,
+        Some(other) => return Err(format!("unknown fillType `{}`", other)),
+    };
+    let stroke_line_cap = match node.attribute((ANDROID_NS, "strokeLineCap")) {
+        Some("round") => LineCap::Round,
+        Some("square") => LineCap::Square,
+        Some("butt") | None => LineCap::Butt,
+        Some(other) => return Err(format!("unknown strokeLineCap `{}`", other)),
+    };
+    let stroke_line_join = match node.attribute((ANDROID_NS, "strokeLineJoin")) {
+        Some("round") => LineJoin::Round,
+        Some("bevel") => LineJoin::Bevel,
+        Some("miter") | None => LineJoin::Miter,
+        Some(other) => return Err(format!("unknown strokeLineJoin `{}`", other)),
+    };
+
+    Ok(Path {
+        path_data: required_attr(node, "pathData")?.to_string(),
+        fill,
+        fill_alpha: number_attr(node, "fillAlpha", 1f64)?,
+        fill_type,
+        stroke,
+        stroke_width: number_attr(node, "strokeWidth", 0f64)?,
+        stroke_alpha: number_attr(node, "strokeAlpha", 1f64)?,
+        stroke_line_cap,
+        stroke_line_join,
+        stroke_miter_limit: number_attr(node, "strokeMiterLimit", 4f64)?,
+    })
+}
+
+/// Look for the `<aapt:attr name="...">` child with the `<gradient>` inside.
+fn aapt_gradient(node: &Node, attr_name: &str) -> Result<Option<Gradient>, String> {
+    let gradient = node
+        .children()
+        .filter(|n| n.is_element())
+        .filter(|n| n.tag_name().namespace() == Some(AAPT_NS) && n.tag_name().name() == "attr")
+        .filter(|n| n.attribute("name") == Some(attr_name))
+        .flat_map(|n| n.children())
+        .find(|n| n.is_element() && n.tag_name().name() == "gradient");
+
+    match gradient {
+        Some(gradient) => parse_gradient(&gradient).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn parse_gradient(node: &Node) -> Result<Gradient, String> {
+    let kind = match node.attribute((ANDROID_NS, "type")).unwrap_or("linear") {
+        "linear" => GradientKind::Linear {
+            start_x: number_attr(node, "startX", 0f64)?,
+            start_y: number_attr(node, "startY", 0f64)?,
+            end_x: number_attr(node, "endX", 0f64)?,
+            end_y: number_attr(node, "endY", 0f64)?,
+        },
+        "radial" => GradientKind::Radial {
+            center_x: number_attr(node, "centerX", 0f64)?,
+            center_y: number_attr(node, "centerY", 0f64)?,
+            radius: number_attr(node, "gradientRadius", 0f64)?,
+        },
+        "sweep" => GradientKind::Sweep,
+        other => return Err(format!("unknown gradient type `{}`", other)),
+    };
+    let tile_mode = match node.attribute((ANDROID_NS, "tileMode")) {
+        Some("repeat") => TileMode::Repeat,
+        Some("mirror") => TileMode::Mirror,
+        Some("clamp") | Some("disabled") | None => TileMode::Clamp,
+        Some(other) => return Err(format!("unknown gradient tileMode `{}`", other)),
+    };
+
+    // Explicit `<item>` stops take precedence over start/center/end colors
+    let mut stops = Vec::new();
+    for item in node
+        .children()
+        .filter(|n| n.is_element() && n.tag_name().name() == "item")
+    {
+        stops.push(GradientStop {
+            offset: number_attr(&item, "offset", 0f64)?,
+            color: parse_color(required_attr(&item, "color")?)?,
+        });
+    }
+    if stops.is_empty() {
+        let colors = [
+            (0f64, node.attribute((ANDROID_NS, "startColor"))),
+            (0.5f64, node.attribute((ANDROID_NS, "centerColor"))),
+            (1f64, node.attribute((ANDROID_NS, "endColor"))),
+        ];
+        for (offset, color) in colors {
+            if let Some(color) = color {
+                stops.push(GradientStop {
+                    offset,
+                    color: parse_color(color)?,
+                });
+            }
+        }
+    }
+
+    Ok(Gradient {
+        kind,
+        tile_mode,
+        stops,
+    })
+}
+
+fn required_attr<'a>(node: &Node<'a, '_>, name: &str) -> Result<&'a str, String> {
+    node.attribute((ANDROID_NS, name)).ok_or_else(|| {
+        format!(
+            "missing attribute `android:{}` in <{}>",
+            name,
+            node.tag_name().name()
+        )
+    })
+}
+
+fn number_attr(node: &Node, name: &str, default: f64) -> Result<f64, String> {
+    match node.attribute((ANDROID_NS, name)) {
+        Some(value) => parse_number(value),
+        None => Ok(default),
+    }
+}
+
+fn parse_number(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("`{}` is not a number", value))
+}
+
+/// Parse dimension like `24dp` or `24.5dp`. Values without units are treated as `dp`.
+fn parse_dimension(value: &str) -> Result<f64, String> {
+    let value = value.trim();
+    let number = ["dp", "dip", "px"]
+        .iter()
+        .find_map(|unit| value.strip_suffix(unit))
+        .unwrap_or(value);
+    parse_number(number)
+}
+
+/// Parse color in one of the formats: `#RGB`, `#ARGB`, `#RRGGBB` or `#AARRGGBB`.
+/// References to resources (`@color/...`, `?attr/...`) can't be resolved outside of the project.
+pub fn parse_color(value: &str) -> Result<Color, String> {
+    let hex = value.trim().strip_prefix('#').ok_or_else(|| {
+        format!(
+            "unsupported color `{}`, only hex colors are supported",
+            value
+        )
+    })?;
+    let digits = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| format!("invalid hex color `{}`", value))?;
+    let (alpha, red, green, blue) = match digits.as_slice() {
+        [r, g, b] => (255, r * 17, g * 17, b * 17),
+        [a, r, g, b] => (a * 17, r * 17, g * 17, b * 17),
+        [r1, r2, g1, g2, b1, b2] => (255, r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2),
+        [a1, a2, r1, r2, g1, g2, b1, b2] => {
+            (a1 * 16 + a2, r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2)
+        }
+        _ => return Err(format!("invalid hex color `{}`", value)),
+    };
+    Ok(Color {
+        alpha,
+        red,
+        green,
+        blue,
+    })
+}
+
+#[test]
+fn test_parse_color_formats() {
+    let red = Color {
+        alpha: 255,
+        red: 255,
+        green: 0,
+        blue: 0,
+    };
+    assert_eq!(parse_color("#F00").unwrap(), red);
+    assert_eq!(parse_color("#FF0000").unwrap(), red);
+    assert_eq!(parse_color("#FFFF0000").unwrap(), red);
+    assert_eq!(parse_color("#80FF0000").unwrap().alpha, 0x80);
+    assert!(parse_color("@color/red").is_err());
+}
+
+#[test]
+fn test_parse_group_and_gradient() {
+    let content = r##"<vector xmlns:android="http://schemas.android.com/apk/res/android"
+        xmlns:aapt="http://schemas.android.com/aapt"
+        android:width="24dp" android:height="24dp"
+        android:viewportWidth="24" android:viewportHeight="24">
+        <group android:rotation="45" android:pivotX="12" android:pivotY="12">
+            <clip-path android:pathData="M0,0h24v24h-24z" />
+            <path android:pathData="M0,0L24,24">
+                <aapt:attr name="android:fillColor">
+                    <gradient android:type="linear" android:startX="0" android:startY="0"
+                        android:endX="24" android:endY="24"
+                        android:startColor="#FF000000" android:endColor="#FFFFFFFF" />
+                </aapt:attr>
+            </path>
+        </group>
+    </vector>"##;
+    let drawable = parse_vector_drawable(content).unwrap();
+    assert_eq!(drawable.width, 24f64);
+    let group = match &drawable.children[0] {
+        Element::Group(group) => group,
+        _ => panic!("expected group"),
+    };
+    assert_eq!(group.rotation, 45f64);
+    assert!(matches!(group.children[0], Element::ClipPath(_)));
+    match &group.children[1] {
+        Element::Path(Path {
+            fill: Some(Paint::Gradient(gradient)),
+            ..
+        }) => assert_eq!(gradient.stops.len(), 2),
+        _ => panic!("expected path with gradient"),
+    }
+}
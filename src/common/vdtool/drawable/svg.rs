@@ -0,0 +1,358 @@
+use std::io::{BufWriter, Write};
+
+use crate::common::vdtool::drawable::model::{
+    Color, Element, FillType, Gradient, GradientKind, Group, LineCap, LineJoin, Paint, Path,
+    TileMode, VectorDrawable,
+};
+
+/// Identifiers generator for `<clipPath>` and gradients declared in `<defs>`.
+struct SvgContext {
+    next_id: usize,
+}
+
+impl SvgContext {
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}_{}", prefix, self.next_id)
+    }
+}
+
+/// Write VectorDrawable as SVG document.
+///
+/// # Arguments
+///
+/// * `w` - Writer for the SVG document
+/// * `drawable` - Parsed VectorDrawable
+pub fn write_svg<W: Write>(
+    w: &mut BufWriter<W>,
+    drawable: &VectorDrawable,
+) -> Result<(), std::io::Error> {
+    let mut context = SvgContext { next_id: 0 };
+
+    // Render header
+    writeln!(
+        w,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.}\" height=\"{:.}\" viewBox=\"0 0 {:.} {:.}\">",
+        drawable.width, drawable.height, drawable.viewport_width, drawable.viewport_height,
+    )?;
+
+    // Render content of every child
+    if drawable.alpha != 1f64 {
+        writeln!(w, "{:s$}<g opacity=\"{:.}\">", "", drawable.alpha, s = 4)?;
+        write_children(w, &drawable.children, 2, &mut context)?;
+        writeln!(w, "{:s$}</g>", "", s = 4)?;
+    } else {
+        write_children(w, &drawable.children, 1, &mut context)?;
+    }
+
+    // Render footer
+    writeln!(w, "</svg>")
+}
+
+fn write_children<W: Write>(
+    w: &mut BufWriter<W>,
+    children: &[Element],
+    depth: usize,
+    context: &mut SvgContext,
+) -> Result<(), std::io::Error> {
+    // A clip-path clips every following sibling, so we wrap them with <g clip-path="...">
+    let mut depth = depth;
+    let mut opened_clip_groups = 0usize;
+    for child in children {
+        match child {
+            Element::Group(group) => write_group(w, group, depth, context)?,
+            Element::Path(path) => write_path(w, path, depth, context)?,
+            Element::ClipPath(clip_path) => {
+                let id = context.next_id("clip");
+                writeln!(w, "{:s$}<defs>", "", s = depth * 4)?;
+                let id = escape_attribute(&id);
+                writeln!(w, "{:s$}<clipPath id=\"{}\">", "", id, s = (depth + 1) * 4)?;
+                writeln!(
+                    w,
+                    "{:s$}<path d=\"{}\" />",
+                    "",
+                    escape_attribute(&clip_path.path_data),
+                    s = (depth + 2) * 4
+                )?;
+                writeln!(w, "{:s$}</clipPath>", "", s = (depth + 1) * 4)?;
+                writeln!(w, "{:s$}</defs>", "", s = depth * 4)?;
+                writeln!(w, "{:s$}<g clip-path=\"url(#{})\">", "", id, s = depth * 4)?;
+                opened_clip_groups += 1;
+                depth += 1;
+            }
+        }
+    }
+    for _ in 0..opened_clip_groups {
+        depth -= 1;
+        writeln!(w, "{:s$}</g>", "", s = depth * 4)?;
+    }
+    Ok(())
+}
+
+fn write_group<W: Write>(
+    w: &mut BufWriter<W>,
+    group: &Group,
+    depth: usize,
+    context: &mut SvgContext,
+) -> Result<(), std::io::Error> {
+    if group.is_identity() {
+        writeln!(w, "{:s$}<g>", "", s = depth * 4)?;
+    } else {
+        // Android applies: scale and rotation around pivot, then translation
+        writeln!(
+            w,
+            "{:s$}<g transform=\"translate({:.},{:.}) rotate({:.}) scale({:.},{:.}) translate({:.},{:.})\">",
+            "",
+            group.translate_x + group.pivot_x,
+            group.translate_y + group.pivot_y,
+            group.rotation,
+            group.scale_x,
+            group.scale_y,
+            -group.pivot_x,
+            -group.pivot_y,
+            s = depth * 4,
+        )?;
+    }
+    write_children(w, &group.children, depth + 1, context)?;
+    writeln!(w, "{:s$}</g>", "", s = depth * 4)
+}
+
+fn write_path<W: Write>(
+    w: &mut BufWriter<W>,
+    path: &Path,
+    depth: usize,
+    context: &mut SvgContext,
+) -> Result<(), std::io::Error> {
+    // Gradients must be declared before usage
+    let fill = write_paint_defs(w, &path.fill, depth, context)?;
+    let stroke = if path.stroke_width > 0f64 {
+        write_paint_defs(w, &path.stroke, depth, context)?
+    } else {
+        None
+    };
+
+    writeln!(w, "{:s$}<path", "", s = depth * 4)?;
+    let s = (depth + 1) * 4;
+
+    match fill {
+        Some((value, opacity)) => {
+            writeln!(w, "{:s$}fill=\"{}\"", "", value, s = s)?;
+            let fill_opacity = opacity * path.fill_alpha;
+            if fill_opacity != 1f64 {
+                writeln!(w, "{:s$}fill-opacity=\"{:.}\"", "", fill_opacity, s = s)?;
+            }
+            if path.fill_type == FillType::EvenOdd {
+                writeln!(w, "{:s$}fill-rule=\"evenodd\"", "", s = s)?;
+            }
+        }
+        None => writeln!(w, "{:s$}fill=\"none\"", "", s = s)?,
+    }
+
+    if let Some((value, opacity)) = stroke {
+        writeln!(w, "{:s$}stroke=\"{}\"", "", value, s = s)?;
+        writeln!(
+            w,
+            "{:s$}stroke-width=\"{:.}\"",
+            "",
+            path.stroke_width,
+            s = s
+        )?;
+        let stroke_opacity = opacity * path.stroke_alpha;
+        if stroke_opacity != 1f64 {
+            writeln!(w, "{:s$}stroke-opacity=\"{:.}\"", "", stroke_opacity, s = s)?;
+        }
+        match path.stroke_line_cap {
+            LineCap::Round => writeln!(w, "{:s$}stroke-linecap=\"round\"", "", s = s)?,
+            LineCap::Square => writeln!(w, "{:s$}stroke-linecap=\"square\"", "", s = s)?,
+            LineCap::Butt => (),
+        }
+        match path.stroke_line_join {
+            LineJoin::Round => writeln!(w, "{:s$}stroke-linejoin=\"round\"", "", s = s)?,
+            LineJoin::Bevel => writeln!(w, "{:s$}stroke-linejoin=\"bevel\"", "", s = s)?,
+            LineJoin::Miter => (),
+        }
+        if path.stroke_miter_limit != 4f64 {
+            writeln!(
+                w,
+                "{:s$}stroke-miterlimit=\"{:.}\"",
+                "",
+                path.stroke_miter_limit,
+                s = s
+            )?;
+        }
+    }
+
+    let path_data = escape_attribute(&path.path_data);
+    writeln!(w, "{:s$}d=\"{}\" />", "", path_data, s = s)
+}
+
+/// Write `<defs>` for gradient paint if necessary.
+/// Returns the value for `fill`/`stroke` attribute and the opacity of the paint.
+fn write_paint_defs<W: Write>(
+    w: &mut BufWriter<W>,
+    paint: &Option<Paint>,
+    depth: usize,
+    context: &mut SvgContext,
+) -> Result<Option<(String, f64)>, std::io::Error> {
+    match paint {
+        None => Ok(None),
+        Some(Paint::Color(color)) => Ok(Some((rgb2hex(color), color.opacity()))),
+        Some(Paint::Gradient(gradient)) => {
+            // SVG has no sweep gradients, so we use the first color as the best approximation
+            if let GradientKind::Sweep = gradient.kind {
+                let color = gradient
+                    .stops
+                    .first()
+                    .map(|stop| stop.color)
+                    .unwrap_or_else(Color::black);
+                return Ok(Some((rgb2hex(&color), color.opacity())));
+            }
+            let id = escape_attribute(&context.next_id("gradient"));
+            writeln!(w, "{:s$}<defs>", "", s = depth * 4)?;
+            write_gradient(w, gradient, &id, depth + 1)?;
+            writeln!(w, "{:s$}</defs>", "", s = depth * 4)?;
+            Ok(Some((format!("url(#{})", id), 1f64)))
+        }
+    }
+}
+
+fn write_gradient<W: Write>(
+    w: &mut BufWriter<W>,
+    gradient: &Gradient,
+    id: &str,
+    depth: usize,
+) -> Result<(), std::io::Error> {
+    let spread_method = match gradient.tile_mode {
+        TileMode::Clamp => "pad",
+        TileMode::Repeat => "repeat",
+        TileMode::Mirror => "reflect",
+    };
+    let tag = match gradient.kind {
+        GradientKind::Linear {
+            start_x,
+            start_y,
+            end_x,
+            end_y,
+        } => {
+            writeln!(
+                w,
+                "{:s$}<linearGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" spreadMethod=\"{}\" x1=\"{:.}\" y1=\"{:.}\" x2=\"{:.}\" y2=\"{:.}\">",
+                "", id, spread_method, start_x, start_y, end_x, end_y, s = depth * 4,
+            )?;
+            "linearGradient"
+        }
+        GradientKind::Radial {
+            center_x,
+            center_y,
+            radius,
+        } => {
+            writeln!(
+                w,
+                "{:s$}<radialGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" spreadMethod=\"{}\" cx=\"{:.}\" cy=\"{:.}\" r=\"{:.}\">",
+                "", id, spread_method, center_x, center_y, radius, s = depth * 4,
+            )?;
+            "radialGradient"
+        }
+        GradientKind::Sweep => unreachable!("Sweep gradients are replaced with colors"),
+    };
+    for stop in &gradient.stops {
+        write!(
+            w,
+            "{:s$}<stop offset=\"{:.}\" stop-color=\"{}\"",
+            "",
+            stop.offset,
+            rgb2hex(&stop.color),
+            s = (depth + 1) * 4,
+        )?;
+        if stop.color.alpha != 255 {
+            write!(w, " stop-opacity=\"{:.}\"", stop.color.opacity())?;
+        }
+        writeln!(w, " />")?;
+    }
+    writeln!(w, "{:s$}</{}>", "", tag, s = depth * 4)
+}
+
+/// Escape the value of an attribute, so characters like `&` and `"` don't break the document.
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn rgb2hex(color: &Color) -> String {
+    format!("#{:02X}{:02X}{:02X}", color.red, color.green, color.blue)
+}
+
+#[test]
+fn test_write_svg() {
+    let content = r##"<vector xmlns:android="http://schemas.android.com/apk/res/android"
+        android:width="24dp" android:height="24dp"
+        android:viewportWidth="48" android:viewportHeight="48">
+        <clip-path android:pathData="M0,0h48v48h-48z" />
+        <path android:pathData="M0,0L48,48" android:fillColor="#80FF0000"
+            android:fillType="evenOdd" android:strokeColor="#00FF00"
+            android:strokeWidth="2" android:strokeLineCap="round" />
+    </vector>"##;
+    let drawable = crate::common::vdtool::drawable::parser::parse_vector_drawable(content).unwrap();
+    let mut writer = BufWriter::new(Vec::new());
+    write_svg(&mut writer, &drawable).unwrap();
+    let svg = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"24\" height=\"24\" viewBox=\"0 0 48 48\">"));
+    assert!(svg.contains("<clipPath id=\"clip_1\">"));
+    assert!(svg.contains("<g clip-path=\"url(#clip_1)\">"));
+    assert!(svg.contains("fill=\"#FF0000\""));
+    assert!(svg.contains("fill-opacity=\"0.50"));
+    assert!(svg.contains("fill-rule=\"evenodd\""));
+    assert!(svg.contains("stroke=\"#00FF00\""));
+    assert!(svg.contains("stroke-width=\"2\""));
+    assert!(svg.contains("stroke-linecap=\"round\""));
+    assert!(svg.contains("d=\"M0,0L48,48\" />"));
+    // Every opened group is closed
+    assert_eq!(svg.matches("<g").count(), svg.matches("</g>").count());
+}
+
+#[test]
+fn test_write_svg_gradient() {
+    let content = r##"<vector xmlns:android="http://schemas.android.com/apk/res/android"
+        xmlns:aapt="http://schemas.android.com/aapt"
+        android:width="24dp" android:height="24dp"
+        android:viewportWidth="24" android:viewportHeight="24">
+        <path android:pathData="M0,0L24,24">
+            <aapt:attr name="android:fillColor">
+                <gradient android:type="radial" android:centerX="12" android:centerY="12"
+                    android:gradientRadius="12" android:tileMode="mirror"
+                    android:startColor="#FF000000" android:endColor="#80FFFFFF" />
+            </aapt:attr>
+        </path>
+    </vector>"##;
+    let drawable = crate::common::vdtool::drawable::parser::parse_vector_drawable(content).unwrap();
+    let mut writer = BufWriter::new(Vec::new());
+    write_svg(&mut writer, &drawable).unwrap();
+    let svg = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+    assert!(svg.contains("<radialGradient id=\"gradient_1\" gradientUnits=\"userSpaceOnUse\" spreadMethod=\"reflect\" cx=\"12\" cy=\"12\" r=\"12\">"));
+    assert!(svg.contains("<stop offset=\"1\" stop-color=\"#FFFFFF\" stop-opacity=\"0.50"));
+    assert!(svg.contains("fill=\"url(#gradient_1)\""));
+}
+
+#[test]
+fn test_write_svg_escapes_attributes() {
+    let content = r##"<vector xmlns:android="http://schemas.android.com/apk/res/android"
+        android:width="24dp" android:height="24dp"
+        android:viewportWidth="24" android:viewportHeight="24">
+        <clip-path android:pathData="M0,0h24v24h-24z &amp; &quot;" />
+        <path android:pathData="M0,0L24,24 &lt;&amp;&gt;" android:fillColor="#FF0000" />
+    </vector>"##;
+    let drawable = crate::common::vdtool::drawable::parser::parse_vector_drawable(content).unwrap();
+    let mut writer = BufWriter::new(Vec::new());
+    write_svg(&mut writer, &drawable).unwrap();
+    let svg = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+    assert!(svg.contains("d=\"M0,0h24v24h-24z &amp; &quot;\""));
+    assert!(svg.contains("d=\"M0,0L24,24 &lt;&amp;&gt;\""));
+    assert!(roxmltree::Document::parse(&svg).is_ok());
+}
@@ -10,6 +10,12 @@ pub enum VectorDrawableError {
     #[error("Can't parse file {0}. Cause: {1}")]
     CannotParseSvg(String, String),
 
+    #[error("Can't read vector drawable {0}. Cause: {1}")]
+    CannotReadXml(String, String),
+
+    #[error("Can't parse vector drawable {0}. Cause: {1}")]
+    CannotParseXml(String, String),
+
     #[error("Can't write to output xml file")]
     CannotWrite(#[from] io::Error),
 }
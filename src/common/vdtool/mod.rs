@@ -1,3 +1,4 @@
+pub mod drawable;
 pub mod error;
 pub mod render;
 pub mod vdtool;
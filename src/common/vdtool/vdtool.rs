@@ -1,13 +1,12 @@
-use std::fs;
 use std::io::BufWriter;
 use std::io::Write;
-use std::path::Path;
 
 use usvg::Node;
 use usvg::Options;
 use usvg::Tree;
 
-use crate::common::fileutils::TEMP_DIR_PATH;
+use crate::common::vdtool::drawable::parser::parse_vector_drawable;
+use crate::common::vdtool::drawable::svg::write_svg;
 use crate::common::vdtool::error::VectorDrawableError;
 
 pub trait ToVectorDrawable {
@@ -20,29 +19,34 @@ pub trait ToVectorDrawable {
         W: Write;
 }
 
-pub fn convert_svg_to_xml(file_path: &String) -> Result<String, VectorDrawableError> {
-    let svg_content = fs::read_to_string(&file_path)
-        .map_err(|e| VectorDrawableError::CannotReadSvg(file_path.clone(), e.to_string()))?;
-
-    let svg_tree = Tree::from_str(&svg_content, &Options::default().to_ref())
-        .map_err(|e| VectorDrawableError::CannotParseSvg(file_path.clone(), e.to_string()))?;
-
-    // Put xml-icon in the location of the original svg icon
-    let original_icon_file_name = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
-    // Make full output path for webp-image.
-    let xml_icon_path = format!("{}/{}.xml", TEMP_DIR_PATH, original_icon_file_name);
-    // write to file
-    let xml_file = fs::OpenOptions::new()
-        .create(true)
-        .truncate(true)
-        .write(true)
-        .open(&xml_icon_path)
-        .unwrap();
-    let mut writer = BufWriter::new(xml_file);
+/// Convert SVG to Android VectorDrawable XML in memory.
+///
+/// # Arguments
+///
+/// * `name` - Name of the converted file or icon, it is used in error descriptions only
+/// * `svg_content` - Content of the SVG file
+pub fn convert_svg_to_xml(name: &str, svg_content: &str) -> Result<Vec<u8>, VectorDrawableError> {
+    let svg_tree = Tree::from_str(svg_content, &Options::default().to_ref())
+        .map_err(|e| VectorDrawableError::CannotParseSvg(name.to_string(), e.to_string()))?;
+
+    let mut writer = BufWriter::new(Vec::new());
     svg_tree.root().to_vector_drawable(&mut writer, None)?;
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
+
+/// Convert Android VectorDrawable XML to SVG in memory.
+///
+/// # Arguments
+///
+/// * `name` - Name of the converted file or icon, it is used in error descriptions only
+/// * `xml_content` - Content of the VectorDrawable XML file
+pub fn convert_xml_to_svg(name: &str, xml_content: &str) -> Result<Vec<u8>, VectorDrawableError> {
+    let drawable = parse_vector_drawable(xml_content)
+        .map_err(|e| VectorDrawableError::CannotParseXml(name.to_string(), e))?;
 
-    // return ok
-    Ok(xml_icon_path)
+    let mut writer = BufWriter::new(Vec::new());
+    write_svg(&mut writer, &drawable)?;
+    writer.into_inner().map_err(|e| e.into_error().into())
 }
 
 impl ToVectorDrawable for Node {
@@ -61,3 +65,46 @@ impl ToVectorDrawable for Node {
         }
     }
 }
+
+#[test]
+fn test_xml_svg_round_trip() {
+    use crate::common::vdtool::drawable::model::{Element, Paint};
+
+    let content = r##"<vector xmlns:android="http://schemas.android.com/apk/res/android"
+        android:width="24dp" android:height="24dp"
+        android:viewportWidth="24" android:viewportHeight="24">
+        <path android:pathData="M2,2L22,2L22,22L2,22Z" android:fillColor="#FF2196F3" />
+        <path android:pathData="M6,12L18,12" android:strokeColor="#FFFF0000"
+            android:strokeWidth="2" />
+    </vector>"##;
+    let svg = convert_xml_to_svg("ic_test.xml", content).unwrap();
+    let xml = convert_svg_to_xml("ic_test.svg", &String::from_utf8(svg).unwrap()).unwrap();
+    let drawable = parse_vector_drawable(&String::from_utf8(xml).unwrap()).unwrap();
+
+    assert_eq!(drawable.width, 24f64);
+    assert_eq!(drawable.viewport_height, 24f64);
+    let paths: Vec<_> = drawable
+        .children
+        .iter()
+        .filter_map(|e| match e {
+            Element::Path(path) => Some(path),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(paths.len(), 2);
+    assert!(
+        matches!(&paths[0].fill, Some(Paint::Color(c)) if (c.red, c.green, c.blue) == (0x21, 0x96, 0xF3))
+    );
+    assert!(
+        matches!(&paths[1].stroke, Some(Paint::Color(c)) if (c.red, c.green, c.blue) == (0xFF, 0, 0))
+    );
+    assert_eq!(paths[1].stroke_width, 2f64);
+}
+
+#[test]
+fn test_convert_invalid_xml() {
+    let error = convert_xml_to_svg("ic_broken.xml", "<vector").unwrap_err();
+    assert!(
+        matches!(error, VectorDrawableError::CannotParseXml(name, _) if name == "ic_broken.xml")
+    );
+}
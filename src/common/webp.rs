@@ -1,46 +1,173 @@
-use image::io::Reader as ImageReader;
-use image::EncodableLayout;
-use webp::Encoder;
+use image::{DynamicImage, RgbaImage};
+use libwebp_sys::{
+    WebPConfig, WebPConfigInitInternal, WebPEncode, WebPMemoryWrite, WebPMemoryWriter,
+    WebPMemoryWriterClear, WebPMemoryWriterInit, WebPPicture, WebPPictureFree,
+    WebPPictureImportRGBA, WebPPictureInitInternal, WebPPreset, WebPValidateConfig,
+    WEBP_ENCODER_ABI_VERSION,
+};
 
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::mem::MaybeUninit;
+use std::os::raw::c_void;
 
 use super::error::AppError;
+use crate::models::config::AndroidImagesWebpConfig;
 
-/// Converts PNG image to WEBP. Returns the path to the WEBP image, or an error with a description.
-/// In case of successful conversion, the function will create a WEPB file next to the original PNG file.
+/// Converts image to WEBP in memory. Returns the content of the WEBP image, or an error with a description.
 ///
 /// # Arguments
 ///
-/// * `file_path` - Path to PNG image
-/// * `quality` - Encoding quality in percents (from 0 to 100). Pass 100% for loseless encoding
+/// * `image_name` - Name of the image, it is used in error descriptions only
+/// * `image` - Decoded image of any color model
+/// * `options` - Encoding options. Pass `lossless: true` for lossless encoding
+pub fn image_to_webp(
+    image_name: &str,
+    image: DynamicImage,
+    options: &AndroidImagesWebpConfig,
+) -> Result<Vec<u8>, AppError> {
+    encode(&normalize(image), options)
+        .map_err(|e| AppError::CannotEncode(image_name.to_string(), e))
+}
+
+/// Convert image of any color model to 8-bit RGBA, which is accepted by the encoder.
 ///
-/// Function uses adopted code from: https://users.rust-lang.org/t/converting-png-jpeg-image-to-webp/71080
-pub fn image_to_webp(file_path: &String, quality: f32) -> Result<String, AppError> {
-    let reader = ImageReader::open(file_path)
-        .map_err(|_| AppError::SourceNotFound(file_path.clone()))?
-        .with_guessed_format()
-        .map_err(|_| AppError::UnderlyingReader(file_path.clone()))?;
-
-    let image = reader
-        .decode()
-        .map_err(|_| AppError::CannotDecode(file_path.clone()))?;
-
-    let encoder =
-        Encoder::from_image(&image).map_err(|_| AppError::CannotEncode(file_path.clone()))?;
-
-    // Create webp encoded image in RAM
-    let webp_memory = encoder.encode(quality);
-    // Put webp-image in the location of the original image
-    let original_image_file_name = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
-    // Make full output path for webp-image
-    let webp_image_path = format!("{}.webp", original_image_file_name);
-
-    File::create(webp_image_path.to_string())
-        .map_err(|e| AppError::WriteWebpTemporarySave(format!("{}", e)))?
-        .write_all(webp_memory.as_bytes())
-        .map_err(|e| AppError::WriteWebpTemporarySave(format!("{}", e)))?;
-
-    Ok(webp_image_path)
+/// PNG decoder has already expanded indexed colors and `tRNS` transparency, so we only
+/// need to add the alpha channel to grayscale and RGB images and to reduce 16-bit channels
+/// to 8-bit ones. Float images are considered as linear and converted to sRGB.
+fn normalize(image: DynamicImage) -> RgbaImage {
+    match image {
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+            let mut rgba = image.into_rgba32f();
+            for pixel in rgba.pixels_mut() {
+                for channel in pixel.0.iter_mut().take(3) {
+                    *channel = linear_to_srgb(*channel);
+                }
+            }
+            DynamicImage::ImageRgba32F(rgba).into_rgba8()
+        }
+        _ => image.into_rgba8(),
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    let value = value.clamp(0f32, 1f32);
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1f32 / 2.4) - 0.055
+    }
+}
+
+/// Encode image with the advanced libwebp config. Unlike the simple encoding API,
+/// it allows to set lossless mode, alpha quality, method and near lossless preprocessing.
+///
+/// Returns a description of the problem in case of encoder failure.
+fn encode(image: &RgbaImage, options: &AndroidImagesWebpConfig) -> Result<Vec<u8>, String> {
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    unsafe {
+        let mut config = MaybeUninit::<WebPConfig>::uninit();
+        if WebPConfigInitInternal(
+            config.as_mut_ptr(),
+            WebPPreset::WEBP_PRESET_DEFAULT,
+            options.quality,
+            WEBP_ENCODER_ABI_VERSION,
+        ) == 0
+        {
+            return Err("libwebp version mismatch".to_string());
+        }
+        let mut config = config.assume_init();
+        config.lossless = options.lossless as i32;
+        config.alpha_quality = options.alpha_quality as i32;
+        config.method = options.method as i32;
+        config.near_lossless = options.near_lossless as i32;
+        if WebPValidateConfig(&config) == 0 {
+            return Err(format!(
+                "invalid webpOptions: quality {}, alphaQuality {}, method {}, nearLossless {}",
+                options.quality, options.alpha_quality, options.method, options.near_lossless,
+            ));
+        }
+
+        let mut picture = MaybeUninit::<WebPPicture>::uninit();
+        if WebPPictureInitInternal(picture.as_mut_ptr(), WEBP_ENCODER_ABI_VERSION) == 0 {
+            return Err("libwebp version mismatch".to_string());
+        }
+        let mut picture = picture.assume_init();
+        // Lossless encoding works with ARGB pixels, lossy one with YUV
+        picture.use_argb = options.lossless as i32;
+        picture.width = width;
+        picture.height = height;
+        if WebPPictureImportRGBA(&mut picture, image.as_ptr(), width * 4) == 0 {
+            WebPPictureFree(&mut picture);
+            return Err("not enough memory to import the image".to_string());
+        }
+
+        let mut writer = MaybeUninit::<WebPMemoryWriter>::uninit();
+        WebPMemoryWriterInit(writer.as_mut_ptr());
+        let mut writer = writer.assume_init();
+        picture.writer = Some(WebPMemoryWrite);
+        picture.custom_ptr = &mut writer as *mut WebPMemoryWriter as *mut c_void;
+
+        let encoded = WebPEncode(&config, &mut picture);
+        let error_code = picture.error_code;
+        WebPPictureFree(&mut picture);
+        let result = if encoded == 0 {
+            Err(format!("libwebp error {:?}", error_code))
+        } else {
+            Ok(std::slice::from_raw_parts(writer.mem, writer.size).to_vec())
+        };
+        WebPMemoryWriterClear(&mut writer);
+        result
+    }
+}
+
+#[test]
+fn test_lossless_encoding_keeps_pixels() {
+    let mut pixels = image::RgbaImage::new(8, 8);
+    for (x, y, pixel) in pixels.enumerate_pixels_mut() {
+        pixel.0 = [(x * 32) as u8, (y * 32) as u8, 128, (x * y * 4) as u8];
+    }
+    let options = AndroidImagesWebpConfig {
+        quality: 100f32,
+        lossless: true,
+        alpha_quality: 100,
+        method: 6,
+        near_lossless: 100,
+    };
+    let webp_memory = encode(&pixels, &options).unwrap();
+    let decoded = image::load_from_memory(&webp_memory).unwrap().into_rgba8();
+    // Fully transparent pixels may lose their color, it's fine
+    for (expected, actual) in pixels.pixels().zip(decoded.pixels()) {
+        if expected.0[3] != 0 {
+            assert_eq!(expected, actual);
+        }
+    }
+
+    let invalid_options = AndroidImagesWebpConfig {
+        method: 7,
+        ..options
+    };
+    assert!(encode(&pixels, &invalid_options).is_err());
+}
+
+#[test]
+fn test_normalize_color_models() {
+    let gray16 = image::ImageBuffer::from_pixel(2, 2, image::Luma([0x8000u16]));
+    let rgba = normalize(DynamicImage::ImageLuma16(gray16));
+    assert_eq!(rgba.get_pixel(0, 0).0, [128, 128, 128, 255]);
+
+    let gray_alpha = image::ImageBuffer::from_pixel(2, 2, image::LumaA([200u8, 100u8]));
+    let rgba = normalize(DynamicImage::ImageLumaA8(gray_alpha));
+    assert_eq!(rgba.get_pixel(0, 0).0, [200, 200, 200, 100]);
+
+    let linear = image::ImageBuffer::from_pixel(2, 2, image::Rgba([0.5f32, 0f32, 1f32, 0.5f32]));
+    let rgba = normalize(DynamicImage::ImageRgba32F(linear));
+    assert_eq!(rgba.get_pixel(0, 0).0, [188, 0, 255, 128]);
+
+    let options = AndroidImagesWebpConfig {
+        quality: 85f32,
+        lossless: false,
+        alpha_quality: 100,
+        method: 4,
+        near_lossless: 100,
+    };
+    assert!(encode(&rgba, &options).is_ok());
 }
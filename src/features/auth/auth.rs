@@ -0,0 +1,345 @@
+use std::io::{stdin, BufRead, BufReader, ErrorKind, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal;
+use crossterm::tty::IsTty;
+use reqwest::Url;
+
+use crate::api::figma::{FigmaApi, FigmaUser};
+use crate::api::oauth::{authorization_url, exchange_code};
+use crate::common::error::AppError;
+use crate::common::http_client::create_http_client;
+use crate::common::renderer::Renderer;
+use crate::feature_auth::view::View;
+use crate::models::credentials::{
+    resolve_token, Credentials, FigmaToken, OAuthCredentials, TokenSource,
+};
+
+/// The user has this time to allow the access in the browser
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Time to wait for the request line from the browser connection
+const CALLBACK_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Show where the token is taken from and whose it is.
+pub fn status(token: &Option<String>) {
+    let renderer = Renderer();
+    renderer.new_line();
+
+    let (figma_token, source) = match resolve_token(token) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+    let token_kind = match figma_token {
+        FigmaToken::Personal(_) => "personal access token",
+        FigmaToken::OAuth(_) => "OAuth access token",
+    };
+    match source {
+        TokenSource::Argument => renderer.render(View::TokenFromArgument),
+        TokenSource::CredentialsFile(path) => {
+            renderer.render(View::TokenFromFile(token_kind.to_string(), path))
+        }
+    }
+    renderer.render(View::CheckingToken);
+    match FigmaApi::from_token(token).and_then(|api| api.get_me()) {
+        Ok(user) => renderer.render(View::TokenValid(user.handle, user.email)),
+        Err(e) => renderer.render(View::Error(e.to_string())),
+    }
+}
+
+/// Check the personal access token and save it to the user level credentials file.
+/// The token is asked from stdin if it is not passed.
+pub fn login(token: &Option<String>) {
+    let renderer = Renderer();
+    renderer.new_line();
+
+    let token = match token {
+        Some(token) => token.trim().to_string(),
+        None => {
+            renderer.render(View::EnterToken);
+            read_token(&renderer).trim().to_string()
+        }
+    };
+    if token.is_empty() {
+        renderer.render(View::Error(AppError::MissingToken.to_string()));
+        return;
+    }
+
+    let figma_token = FigmaToken::Personal(token.clone());
+    let user = match check_token(&figma_token, &renderer) {
+        Ok(user) => user,
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+    save_credentials(Some(token), None, user, &renderer);
+}
+
+/// Read the token from the terminal without echo, so it doesn't stay on the screen.
+/// The token is read as a plain line when stdin is not a terminal, e.g. it is piped.
+fn read_token(renderer: &Renderer) -> String {
+    if !stdin().is_tty() || terminal::enable_raw_mode().is_err() {
+        let mut token = String::new();
+        stdin().read_line(&mut token).unwrap_or_default();
+        return token;
+    }
+    let mut token = String::new();
+    while let Ok(event) = event::read() {
+        if let Event::Key(KeyEvent { code, modifiers }) = event {
+            match code {
+                KeyCode::Enter => break,
+                KeyCode::Esc => {
+                    token.clear();
+                    break;
+                }
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    token.clear();
+                    break;
+                }
+                KeyCode::Char(c) => token.push(c),
+                KeyCode::Backspace => {
+                    token.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    terminal::disable_raw_mode().unwrap_or_default();
+    renderer.new_line();
+    token
+}
+
+/// Authorize Figma OAuth app in the browser and save the tokens to the user level credentials file.
+///
+/// Figma redirects to the local server with the code after the authorization,
+/// so `http://localhost:<port>/callback` must be in the callback urls of the app.
+///
+/// # Arguments
+///
+/// * `client_id` - Client id of Figma OAuth app
+/// * `client_secret` - Client secret of Figma OAuth app
+/// * `port` - port of the local server for the redirect
+/// * `scope` - Comma separated scopes of the access
+pub fn oauth_login(client_id: &str, client_secret: &str, port: u16, scope: &str) {
+    let renderer = Renderer();
+    renderer.new_line();
+
+    let redirect_uri = format!("http://localhost:{}/callback", port);
+    let state = match random_state() {
+        Ok(state) => state,
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            renderer.render(View::Error(
+                AppError::OAuthAuthorization(format!("can't listen on port {}: {}", port, e))
+                    .to_string(),
+            ));
+            return;
+        }
+    };
+    renderer.render(View::OpenUrl(authorization_url(
+        client_id,
+        &redirect_uri,
+        scope,
+        &state,
+    )));
+    renderer.new_line();
+
+    renderer.render(View::WaitingForAuthorization);
+    let credentials = receive_code(&listener, &state, AUTHORIZATION_TIMEOUT).and_then(|code| {
+        exchange_code(client_id, client_secret, &redirect_uri, &code)
+            .map(|response| OAuthCredentials::new(client_id, client_secret, response))
+    });
+    let credentials = match credentials {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+
+    let user = match check_token(&FigmaToken::OAuth(credentials.clone()), &renderer) {
+        Ok(user) => user,
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+    save_credentials(None, Some(credentials), user, &renderer);
+}
+
+fn check_token(token: &FigmaToken, renderer: &Renderer) -> Result<FigmaUser, AppError> {
+    renderer.render(View::CheckingToken);
+    FigmaApi::new(create_http_client(token)?).get_me()
+}
+
+/// The latest login replaces the credentials of the other kind.
+fn save_credentials(
+    personal_token: Option<String>,
+    oauth: Option<OAuthCredentials>,
+    user: FigmaUser,
+    renderer: &Renderer,
+) {
+    let saved = Credentials::from_file().and_then(|mut credentials| {
+        credentials.personal_token = personal_token;
+        credentials.oauth = oauth;
+        credentials.save()
+    });
+    match saved {
+        Ok(path) => renderer.render(View::LoggedIn(user.handle, path)),
+        Err(e) => renderer.render(View::Error(e.to_string())),
+    }
+}
+
+/// Random `state` of the authorization, so the redirect can't be forged by another site.
+fn random_state() -> Result<String, AppError> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| AppError::OAuthAuthorization(format!("can't generate state: {}", e)))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Wait for Figma redirect to the local server and take the code from it.
+/// Fails if there is no redirect during the `timeout`.
+fn receive_code(
+    listener: &TcpListener,
+    state: &str,
+    timeout: Duration,
+) -> Result<String, AppError> {
+    let deadline = Instant::now() + timeout;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| AppError::OAuthAuthorization(e.to_string()))?;
+    while Instant::now() < deadline {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            Err(_) => continue,
+        };
+        // A browser connection which sends nothing must not block the waiting
+        stream.set_nonblocking(false).unwrap_or_default();
+        stream
+            .set_read_timeout(Some(CALLBACK_READ_TIMEOUT))
+            .unwrap_or_default();
+        let mut request_line = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut request_line)
+            .unwrap_or_default();
+
+        // Browsers also ask for favicon and so on
+        let result = match parse_callback(&request_line, state) {
+            Some(result) => result,
+            None => {
+                write!(
+                    stream,
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap_or_default();
+                continue;
+            }
+        };
+        let page = match &result {
+            Ok(_) => "Authorization is done, you can close this tab and return to the terminal.",
+            Err(_) => "Authorization failed, see the details in the terminal.",
+        };
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            page.len(),
+            page,
+        )
+        .unwrap_or_default();
+        return result.map_err(AppError::OAuthAuthorization);
+    }
+    Err(AppError::OAuthAuthorization(format!(
+        "there is no redirect from the browser in {} seconds",
+        timeout.as_secs()
+    )))
+}
+
+/// Parse the request line of the redirect, e.g. `GET /callback?code=...&state=... HTTP/1.1`.
+/// Returns `None` if it is a request to another path.
+fn parse_callback(request_line: &str, state: &str) -> Option<Result<String, String>> {
+    let target = request_line.split_whitespace().nth(1)?;
+    let url = Url::parse(&format!("http://localhost{}", target)).ok()?;
+    if url.path() != "/callback" {
+        return None;
+    }
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.to_string())
+    };
+    if let Some(error) = param("error") {
+        return Some(Err(error));
+    }
+    if param("state").as_deref() != Some(state) {
+        return Some(Err("state of the redirect doesn't match".to_string()));
+    }
+    Some(param("code").ok_or_else(|| "there is no code in the redirect".to_string()))
+}
+
+#[test]
+fn test_parse_callback() {
+    assert_eq!(
+        parse_callback("GET /callback?code=abc&state=xyz HTTP/1.1", "xyz"),
+        Some(Ok("abc".to_string()))
+    );
+    assert_eq!(
+        parse_callback(
+            "GET /callback?error=access_denied&state=xyz HTTP/1.1",
+            "xyz"
+        ),
+        Some(Err("access_denied".to_string()))
+    );
+    assert!(matches!(
+        parse_callback("GET /callback?code=abc&state=other HTTP/1.1", "xyz"),
+        Some(Err(_))
+    ));
+    assert_eq!(parse_callback("GET /favicon.ico HTTP/1.1", "xyz"), None);
+}
+
+#[test]
+fn test_random_state() {
+    let state = random_state().unwrap();
+    assert_eq!(state.len(), 64);
+    assert!(state.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(state, random_state().unwrap());
+}
+
+#[test]
+fn test_receive_code() {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let address = listener.local_addr().unwrap();
+    let browser = thread::spawn(move || {
+        let mut stream = std::net::TcpStream::connect(address).unwrap();
+        write!(stream, "GET /callback?code=abc&state=xyz HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        std::io::Read::read_to_string(&mut stream, &mut response).unwrap();
+        response
+    });
+
+    let code = receive_code(&listener, "xyz", Duration::from_secs(10));
+
+    assert_eq!(code.unwrap(), "abc");
+    assert!(browser.join().unwrap().starts_with("HTTP/1.1 200 OK"));
+    assert!(matches!(
+        receive_code(&listener, "xyz", Duration::from_millis(200)),
+        Err(AppError::OAuthAuthorization(_))
+    ));
+}
@@ -0,0 +1,4 @@
+pub mod auth;
+pub mod view;
+
+pub use auth::{login, oauth_login, status};
@@ -0,0 +1,70 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    TokenFromArgument,
+    TokenFromFile(String, String),
+    CheckingToken,
+    TokenValid(String, Option<String>),
+    EnterToken,
+    OpenUrl(String),
+    WaitingForAuthorization,
+    LoggedIn(String, String),
+    Error(String),
+}
+
+impl Renderable for View {
+    fn render(&self) -> String {
+        match self {
+            View::TokenFromArgument => format!(
+                "{} token from `-t` argument or `FIGMA_PERSONAL_TOKEN` env variable\n",
+                "Using".indent().bold().cyan(),
+            ),
+            View::TokenFromFile(token_kind, path) => format!(
+                "{} {} from credentials file {}\n",
+                "Using".indent().bold().cyan(),
+                &token_kind,
+                &path,
+            ),
+            View::CheckingToken => {
+                format!("{} token with Figma API", "Checking".indent().bold().cyan())
+            }
+            View::TokenValid(handle, email) => match email {
+                Some(email) => format!(
+                    "{} token of user {} ({})",
+                    "Valid".indent().bold().green(),
+                    &handle,
+                    &email,
+                ),
+                None => format!(
+                    "{} token of user {}",
+                    "Valid".indent().bold().green(),
+                    &handle,
+                ),
+            },
+            View::EnterToken => format!(
+                "{} Figma personal access token:",
+                "Paste".indent().bold().cyan(),
+            ),
+            View::OpenUrl(url) => format!(
+                "{} this url in the browser to authorize the app: {}",
+                "Open".indent().bold().cyan(),
+                &url,
+            ),
+            View::WaitingForAuthorization => format!(
+                "{} for the authorization in the browser...",
+                "Waiting".indent().bold().cyan(),
+            ),
+            View::LoggedIn(handle, path) => format!(
+                "{} as {}, the token is saved to {}",
+                "Logged in".indent().bold().green(),
+                &handle,
+                &path,
+            ),
+            View::Error(description) => {
+                format!("{} {}", "Error".indent().bold().red(), &description)
+            }
+        }
+    }
+}
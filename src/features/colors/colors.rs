@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+
+use crate::common::error::AppError;
+use crate::common::fetching::FetcherEntry;
+use crate::common::fileutils::{is_unchanged, StagedFiles};
+use crate::common::gathering::gathering::gather_names;
+use crate::common::renderer::Renderer;
+use crate::common::res_name::{given_res_name, validate_res_names};
+use crate::feature_colors::view::View;
+use crate::models::config::ResourceType;
+use crate::models::figma::Color;
+
+#[derive(Debug, Clone)]
+struct ColorInfo {
+    user_name: String,
+    /// Name of the node in Figma, with `_light` or `_dark` suffix for themed colors
+    figma_name: String,
+    res_name: String,
+    night: bool,
+}
+
+impl ColorInfo {
+    fn values_dir_name(&self) -> String {
+        values_dir_name(self.night)
+    }
+}
+
+fn values_dir_name(night: bool) -> String {
+    if night {
+        "values-night".to_string()
+    } else {
+        "values".to_string()
+    }
+}
+
+/// Export colors found by the fetcher, which is done for `colors` targets of `fxa export`.
+///
+/// Colors are taken from the solid fills of the nodes and written to one file in `values` dir,
+/// and `values-night` dir for the `_dark` variants of themed colors. The files are rewritten
+/// entirely, so they contain only the exported colors.
+pub fn export_fetched_colors(
+    fetcher_entry: FetcherEntry,
+    color_names: &[String],
+    allow_partial: bool,
+) -> Result<(), AppError> {
+    let renderer = Renderer();
+    let (app_config, names_to_ids, names_to_colors, given_res_names) = (
+        fetcher_entry.app_config,
+        fetcher_entry.image_names_to_ids,
+        fetcher_entry.image_names_to_colors,
+        fetcher_entry.image_names_to_res_names,
+    );
+
+    let frame_name = app_config.frame_names(&ResourceType::Colors);
+    let naming = &app_config.common.colors.naming;
+    let colors_for_export: Vec<ColorInfo> = gather_names(
+        &app_config,
+        &frame_name,
+        color_names,
+        &names_to_ids,
+        true,
+        |e| ColorInfo {
+            user_name: e.user_name.clone(),
+            figma_name: e.figma_name,
+            res_name: given_res_name(&e.user_name, &given_res_names, naming),
+            night: e.night,
+        },
+    );
+
+    // Invalid names would fail only in the Android build, so nothing is written
+    validate_res_names(
+        colors_for_export
+            .iter()
+            .map(|e| (&e.user_name, &e.res_name)),
+        naming,
+        |error| {
+            renderer.render(View::Error(error.to_string()));
+            renderer.new_line();
+        },
+    )?;
+
+    // Hex values of the colors by their res names, for `values` and `values-night` dirs
+    let mut day_colors: BTreeMap<String, String> = BTreeMap::new();
+    let mut night_colors: BTreeMap<String, String> = BTreeMap::new();
+    let mut failed_count = 0usize;
+    for color in colors_for_export {
+        match names_to_colors.get(&color.figma_name) {
+            Some(fill) => {
+                let hex = color_hex(fill);
+                renderer.render(View::FoundColor(
+                    color.user_name.clone(),
+                    color.values_dir_name(),
+                    hex.clone(),
+                ));
+                let colors = if color.night {
+                    &mut night_colors
+                } else {
+                    &mut day_colors
+                };
+                colors.insert(color.res_name, hex);
+            }
+            None => {
+                let error = AppError::ColorWithoutFill(color.figma_name.clone());
+                renderer.render(View::Error(error.to_string()));
+                failed_count += 1;
+            }
+        }
+        renderer.new_line();
+    }
+
+    if failed_count > 0 && !allow_partial {
+        return Err(AppError::ExportAborted(failed_count));
+    }
+
+    let main_res = app_config
+        .main_res_colors()
+        .expect("Validation is done in fetcher");
+    let file_name = &app_config.android.colors.file_name;
+    let mut staged_files = StagedFiles::default();
+    let mut unchanged_count = 0usize;
+    for (night, colors) in [(false, day_colors), (true, night_colors)] {
+        let path = format!("{}/{}/{}", &main_res, values_dir_name(night), file_name);
+        // The night file of the colors which are no longer themed is left over otherwise
+        if colors.is_empty() {
+            staged_files.stage_removal(path);
+            continue;
+        }
+        let content = colors_xml(&colors);
+        if is_unchanged(&path, &content) {
+            renderer.render(View::ColorsUnchanged(path));
+            unchanged_count += 1;
+        } else {
+            renderer.render(View::ColorsWritten(path.clone(), colors.len()));
+            staged_files.stage(path, content);
+        }
+        renderer.new_line();
+    }
+
+    let files_count = staged_files.len();
+    staged_files.commit()?;
+    renderer.render(View::Done {
+        message: Some(format!(
+            "{} files are written to the project, {} files are unchanged",
+            files_count, unchanged_count
+        )),
+    });
+    Ok(())
+}
+
+/// Returns the color in Android format: `#RRGGBB`, or `#AARRGGBB` if it is translucent.
+fn color_hex(color: &Color) -> String {
+    let channel = |value: f32| (value.clamp(0f32, 1f32) * 255f32).round() as u8;
+    let (r, g, b, a) = (
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+        channel(color.a),
+    );
+    if a == u8::MAX {
+        format!("#{:02X}{:02X}{:02X}", r, g, b)
+    } else {
+        format!("#{:02X}{:02X}{:02X}{:02X}", a, r, g, b)
+    }
+}
+
+/// Returns the content of the values file with the colors, sorted by their names.
+fn colors_xml(colors: &BTreeMap<String, String>) -> Vec<u8> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n");
+    for (res_name, hex) in colors {
+        xml.push_str(&format!(
+            "    <color name=\"{}\">{}</color>\n",
+            res_name, hex
+        ));
+    }
+    xml.push_str("</resources>\n");
+    xml.into_bytes()
+}
+
+#[test]
+fn test_color_hex() {
+    let color = |r: f32, g: f32, b: f32, a: f32| color_hex(&Color { r, g, b, a });
+    assert_eq!(color(0.38431373, 0f32, 0.93333334, 1f32), "#6200EE");
+    assert_eq!(color(0f32, 0f32, 0f32, 0.5f32), "#80000000");
+    assert_eq!(color(1f32, 1f32, 1f32, 0f32), "#00FFFFFF");
+}
+
+#[test]
+fn test_export_fetched_colors() {
+    use crate::models::config::AppConfig;
+    use std::collections::HashMap;
+    use std::fs;
+
+    let dir = tempfile::tempdir().unwrap();
+    let main_res = dir.path().to_str().unwrap().to_string();
+    // Nodes of the frame with the alpha of their red fills, `None` for nodes without fills
+    let fetcher_entry = |nodes: &[(&str, Option<f32>)]| {
+        let app_config: AppConfig = serde_yaml::from_str(&format!(
+            "figma:\n  fileId: FILE\nandroid:\n  colors:\n    mainRes: {}\n",
+            main_res
+        ))
+        .unwrap();
+        FetcherEntry {
+            app_config,
+            from_cache: false,
+            image_names_to_ids: nodes
+                .iter()
+                .enumerate()
+                .map(|(index, (name, _))| (name.to_string(), format!("1:{}", index)))
+                .collect(),
+            image_names_to_files: HashMap::new(),
+            image_names_to_bounds: HashMap::new(),
+            image_names_to_res_names: HashMap::new(),
+            image_names_to_colors: nodes
+                .iter()
+                .filter_map(|(name, alpha)| {
+                    alpha.map(|a| {
+                        let color = Color {
+                            r: 1f32,
+                            g: 0f32,
+                            b: 0f32,
+                            a,
+                        };
+                        (name.to_string(), color)
+                    })
+                })
+                .collect(),
+            file_versions: HashMap::new(),
+            config_hash: String::new(),
+            node_url: None,
+            target: None,
+        }
+    };
+    let read = |dir_name: &str| {
+        fs::read_to_string(format!("{}/{}/figma_colors.xml", main_res, dir_name)).ok()
+    };
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+    let entry = fetcher_entry(&[
+        ("surface_light", Some(1f32)),
+        ("surface_dark", Some(0.5f32)),
+        ("error", Some(1f32)),
+    ]);
+    export_fetched_colors(entry, &names(&["surface", "error"]), false).unwrap();
+    assert_eq!(
+        read("values").unwrap(),
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n    \
+        <color name=\"error\">#FF0000</color>\n    \
+        <color name=\"surface\">#FF0000</color>\n</resources>\n"
+    );
+    assert!(read("values-night")
+        .unwrap()
+        .contains("<color name=\"surface\">#80FF0000</color>"));
+
+    // Nothing is written if a color has no fill, unless partial export is allowed
+    let nodes = [("primary", Some(1f32)), ("broken", None)];
+    let result =
+        export_fetched_colors(fetcher_entry(&nodes), &names(&["primary", "broken"]), false);
+    assert!(matches!(result, Err(AppError::ExportAborted(1))));
+    assert!(read("values").unwrap().contains("surface"));
+
+    export_fetched_colors(fetcher_entry(&nodes), &names(&["primary", "broken"]), true).unwrap();
+    let values = read("values").unwrap();
+    assert!(values.contains("<color name=\"primary\">#FF0000</color>"));
+    assert!(!values.contains("surface"));
+    assert_eq!(read("values-night"), None);
+}
@@ -0,0 +1,46 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    FoundColor(String, String, String),
+    ColorsWritten(String, usize),
+    ColorsUnchanged(String),
+    Error(String),
+    Done { message: Option<String> },
+}
+
+impl Renderable for View {
+    fn render(&self) -> String {
+        match self {
+            View::FoundColor(color_name, dir_name, hex) => format!(
+                "{} color {} ({}) {}",
+                "Found".indent().bold().green(),
+                &color_name,
+                &dir_name,
+                &hex,
+            ),
+            View::ColorsWritten(path, count) => format!(
+                "{} {} colors to {}",
+                "Exported".indent().bold().green(),
+                count,
+                &path,
+            ),
+            View::ColorsUnchanged(path) => format!(
+                "{} colors in {}",
+                "Unchanged".indent().bold().green(),
+                &path,
+            ),
+            View::Error(description) => {
+                format!("{} {}", "Error".indent().bold().red(), &description)
+            }
+            View::Done { message } => {
+                if let Some(m) = message {
+                    format!("{} {}", "Done".indent().bold().green(), &m)
+                } else {
+                    format!("{}", "Done".indent().bold().green())
+                }
+            }
+        }
+    }
+}
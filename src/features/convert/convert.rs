@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::Path;
+
+use crate::common::error::AppError;
+use crate::common::fileutils::{create_dir, write_file_atomically};
+use crate::common::renderer::Renderer;
+use crate::common::vdtool::error::VectorDrawableError;
+use crate::common::vdtool::vdtool::{convert_svg_to_xml, convert_xml_to_svg};
+use crate::feature_convert::view::View;
+use crate::models::entrypoint::ConvertFormat;
+
+impl ConvertFormat {
+    fn extension(&self) -> String {
+        match self {
+            ConvertFormat::Xml => "xml".to_string(),
+            ConvertFormat::Svg => "svg".to_string(),
+        }
+    }
+
+    fn source_extension(&self) -> String {
+        match self {
+            ConvertFormat::Xml => "svg".to_string(),
+            ConvertFormat::Svg => "xml".to_string(),
+        }
+    }
+}
+
+pub fn convert(format: &ConvertFormat, output_dir: &Option<String>, files: &[String]) {
+    let renderer = Renderer();
+    renderer.new_line();
+
+    for file in files {
+        match convert_file(format, output_dir, file, &renderer) {
+            Ok(()) => (),
+            Err(e) => renderer.render(View::Error(e.to_string())),
+        }
+        renderer.new_line();
+    }
+
+    renderer.render(View::Done);
+}
+
+fn convert_file(
+    format: &ConvertFormat,
+    output_dir: &Option<String>,
+    file: &String,
+    renderer: &Renderer,
+) -> Result<(), AppError> {
+    let path = Path::new(file);
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if extension != format.source_extension() {
+        return Err(AppError::UnexpectedConvertSource(
+            file.clone(),
+            format.extension(),
+            format.source_extension(),
+        ));
+    }
+
+    // Converted file is saved next to the original one, if output dir is not specified
+    let output_dir = match output_dir {
+        Some(dir) => dir.clone(),
+        None => path
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| ".".to_string()),
+    };
+    let file_stem = path.file_stem().unwrap().to_string_lossy();
+    let output_path = format!("{}/{}.{}", output_dir, file_stem, format.extension());
+
+    renderer.render(View::Converting(file.clone(), format.extension()));
+    let source_content = fs::read_to_string(file).map_err(|e| match format {
+        ConvertFormat::Xml => VectorDrawableError::CannotReadSvg(file.clone(), e.to_string()),
+        ConvertFormat::Svg => VectorDrawableError::CannotReadXml(file.clone(), e.to_string()),
+    });
+    let converted_content = match format {
+        ConvertFormat::Xml => source_content
+            .and_then(|content| convert_svg_to_xml(file, &content))
+            .map_err(AppError::CannotConvertToXml)?,
+        ConvertFormat::Svg => source_content
+            .and_then(|content| convert_xml_to_svg(file, &content))
+            .map_err(AppError::CannotConvertToSvg)?,
+    };
+
+    create_dir(&output_dir)
+        .map_err(|e| AppError::CannotSaveConverted(output_path.clone(), e.to_string()))?;
+    write_file_atomically(&output_path, &converted_content)
+        .map_err(|e| AppError::CannotSaveConverted(output_path.clone(), e.to_string()))?;
+
+    renderer.render(View::Converted(file.clone(), output_path));
+    Ok(())
+}
@@ -0,0 +1,4 @@
+pub mod convert;
+pub mod view;
+
+pub use convert::convert;
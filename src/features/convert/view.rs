@@ -0,0 +1,35 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    Converting(String, String),
+    Converted(String, String),
+    Error(String),
+    Done,
+}
+
+impl Renderable for View {
+    fn render(&self) -> String {
+        match self {
+            View::Converting(file_name, format) => format!(
+                "{} {} to {}",
+                "Converting".indent().bold().cyan(),
+                &file_name,
+                &format,
+            ),
+            View::Converted(file_name, output_file_name) => format!(
+                "{} {} to {}",
+                "Converted".indent().bold().green(),
+                &file_name,
+                &output_file_name,
+            ),
+            View::Error(description) => {
+                format!("{} {}", "Error".indent().bold().red(), &description)
+            }
+            View::Done => {
+                format!("{}", "Done".indent().bold().green())
+            }
+        }
+    }
+}
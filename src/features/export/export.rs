@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use crate::api::figma::FigmaApi;
+use crate::common::error::AppError;
+use crate::common::fetching::{
+    fetch_sources, read_targets_config, validate_target_config, FetchedDocuments, FetcherTarget,
+};
+use crate::common::gathering::gathering::all_names;
+use crate::common::renderer::Renderer;
+use crate::feature_colors::export_fetched_colors;
+use crate::feature_export::view::View;
+use crate::feature_icons::export_fetched_icons;
+use crate::feature_images::export_fetched_images;
+use crate::models::config::{AppConfig, ResourceType, TargetConfig};
+
+/// Export the targets of the config in one pass.
+///
+/// The config is read once, and every Figma file is fetched once and shared by all targets
+/// which use it.
+///
+/// # Arguments
+///
+/// * `token` - Figma personal access token
+/// * `yaml_config_path` - config with `targets`
+/// * `target_names` - names of the targets to export, all targets are exported if it is empty
+/// * `verify` - compare rendered VectorDrawables of icon targets with the original SVGs
+/// * `keep_temp_files` - save intermediate files to `.fxa` dir for debugging
+/// * `allow_partial` - write successfully exported resources even if some of them failed
+pub fn export(
+    token: &Option<String>,
+    yaml_config_path: &String,
+    target_names: &[String],
+    verify: bool,
+    keep_temp_files: bool,
+    allow_partial: bool,
+) {
+    let renderer = Renderer();
+    let api = match FigmaApi::from_token(token) {
+        Ok(api) => api,
+        Err(e) => {
+            renderer.new_line();
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+
+    let (app_config, config_hash) = match read_targets_config(yaml_config_path, &renderer) {
+        Ok(result) => result,
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+    let targets = match select_targets(&app_config.targets, target_names, yaml_config_path) {
+        Ok(targets) => targets,
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+
+    let flags = ExportFlags {
+        verify,
+        keep_temp_files,
+        allow_partial,
+    };
+    if let Err(e) = export_targets(
+        &api,
+        &app_config,
+        &config_hash,
+        &targets,
+        flags,
+        yaml_config_path,
+    ) {
+        renderer.render(View::Error(e.to_string()));
+    }
+}
+
+/// Flags of `fxa export` which are applied to every target.
+#[derive(Clone, Copy)]
+pub struct ExportFlags {
+    pub verify: bool,
+    pub keep_temp_files: bool,
+    pub allow_partial: bool,
+}
+
+/// Export the targets one by one, fetching every Figma file only once for all of them.
+/// A failed target doesn't stop the others. Returns an error if any target has failed.
+pub fn export_targets(
+    api: &FigmaApi,
+    app_config: &AppConfig,
+    config_hash: &str,
+    targets: &[TargetConfig],
+    flags: ExportFlags,
+    yaml_config_path: &String,
+) -> Result<(), AppError> {
+    check_colors_outputs(app_config, targets)?;
+    let renderer = Renderer();
+    let mut documents: FetchedDocuments = HashMap::new();
+    let mut failed_count = 0usize;
+    for target in targets {
+        renderer.render(View::Target {
+            name: target.name.clone(),
+            resource_type: format!("{:?}", target.resource_type).to_lowercase(),
+            frame_name: target.figma_frame_name.clone(),
+        });
+        if let Err(e) = export_target(
+            api,
+            app_config,
+            config_hash,
+            target,
+            flags,
+            yaml_config_path,
+            &mut documents,
+        ) {
+            renderer.render(View::Error(e.to_string()));
+            renderer.new_line();
+            failed_count += 1;
+        }
+    }
+    if failed_count > 0 {
+        return Err(AppError::TargetsFailed(failed_count));
+    }
+    Ok(())
+}
+
+fn export_target(
+    api: &FigmaApi,
+    app_config: &AppConfig,
+    config_hash: &str,
+    target: &TargetConfig,
+    flags: ExportFlags,
+    yaml_config_path: &String,
+    documents: &mut FetchedDocuments,
+) -> Result<(), AppError> {
+    let renderer = Renderer();
+    // Every target gets its own copy of the config with its frame as the only source
+    let target_config = config_for_target(app_config, target, yaml_config_path)?;
+    let fetcher_target = match target.resource_type {
+        ResourceType::Images => FetcherTarget::Images,
+        ResourceType::Icons => FetcherTarget::Icons,
+        ResourceType::Colors => FetcherTarget::Colors,
+    };
+    let mut fetcher_entry = fetch_sources(
+        api,
+        target_config,
+        config_hash.to_string(),
+        fetcher_target,
+        &target.names,
+        documents,
+        &renderer,
+    )?;
+    fetcher_entry.target = Some(target.name.clone());
+    let names = if target.names.is_empty() {
+        all_names(&fetcher_entry.image_names_to_ids)
+    } else {
+        target.names.clone()
+    };
+    match target.resource_type {
+        ResourceType::Images => export_fetched_images(
+            api,
+            fetcher_entry,
+            &names,
+            yaml_config_path,
+            flags.keep_temp_files,
+            flags.allow_partial,
+        ),
+        ResourceType::Icons => export_fetched_icons(
+            api,
+            fetcher_entry,
+            &names,
+            yaml_config_path,
+            flags.verify,
+            flags.keep_temp_files,
+            flags.allow_partial,
+        ),
+        ResourceType::Colors => export_fetched_colors(fetcher_entry, &names, flags.allow_partial),
+    }
+}
+
+/// Returns the config of the target, which is checked for the `mainRes` of the target.
+pub fn config_for_target(
+    app_config: &AppConfig,
+    target: &TargetConfig,
+    yaml_config_path: &String,
+) -> Result<AppConfig, AppError> {
+    let target_config = app_config.clone().for_target(target)?;
+    validate_target_config(&target_config, &target.resource_type, yaml_config_path)?;
+    Ok(target_config)
+}
+
+/// Colors targets rewrite their files entirely, so two of them with the same output file would
+/// silently overwrite each other's colors. Targets with invalid configs are reported on export.
+fn check_colors_outputs(app_config: &AppConfig, targets: &[TargetConfig]) -> Result<(), AppError> {
+    let mut output_paths: HashMap<String, &String> = HashMap::new();
+    for target in targets
+        .iter()
+        .filter(|target| target.resource_type == ResourceType::Colors)
+    {
+        let target_config = match app_config.clone().for_target(target) {
+            Ok(target_config) => target_config,
+            Err(_) => continue,
+        };
+        let main_res = match target_config.main_res_colors() {
+            Some(main_res) => main_res,
+            None => continue,
+        };
+        let path = format!(
+            "{}/values/{}",
+            main_res, target_config.android.colors.file_name
+        );
+        if let Some(other) = output_paths.get(&path) {
+            return Err(AppError::ConflictingColorsTargets(
+                path,
+                other.to_string(),
+                target.name.clone(),
+            ));
+        }
+        output_paths.insert(path, &target.name);
+    }
+    Ok(())
+}
+
+/// Returns the targets with the given names in their order, or all targets if no names are given.
+fn select_targets(
+    targets: &[TargetConfig],
+    target_names: &[String],
+    yaml_config_path: &str,
+) -> Result<Vec<TargetConfig>, AppError> {
+    if targets.is_empty() {
+        return Err(AppError::AppConfigMissingTargets(
+            yaml_config_path.to_string(),
+        ));
+    }
+    if target_names.is_empty() {
+        return Ok(targets.to_vec());
+    }
+    target_names
+        .iter()
+        .map(|name| {
+            targets
+                .iter()
+                .find(|target| &target.name == name)
+                .cloned()
+                .ok_or_else(|| {
+                    let available = targets
+                        .iter()
+                        .map(|target| target.name.clone())
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    AppError::UnknownTarget(name.clone(), available)
+                })
+        })
+        .collect()
+}
+
+#[test]
+fn test_check_colors_outputs() {
+    let app_config: AppConfig = serde_yaml::from_str(
+        r#"
+        figma:
+            fileId: PRODUCT
+        android:
+            mainRes: ./core-ui/res
+        targets:
+            - name: brand-colors
+              resourceType: colors
+              figmaFrameName: Brand
+            - name: payments-colors
+              resourceType: colors
+              figmaFrameName: Payments
+              mainRes: ./feature-payments/res
+            - name: promo-colors
+              resourceType: colors
+              figmaFrameName: Promo
+              mainRes: ./core-ui/res
+        "#,
+    )
+    .unwrap();
+    let targets = &app_config.targets;
+
+    assert!(check_colors_outputs(&app_config, &targets[..2]).is_ok());
+    match check_colors_outputs(&app_config, targets) {
+        Err(AppError::ConflictingColorsTargets(path, first, second)) => {
+            assert_eq!(path, "./core-ui/res/values/figma_colors.xml");
+            assert_eq!(
+                (first.as_str(), second.as_str()),
+                ("brand-colors", "promo-colors")
+            );
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+    // Only the selected targets are checked
+    assert!(check_colors_outputs(&app_config, &targets[1..]).is_ok());
+}
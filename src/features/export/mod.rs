@@ -0,0 +1,4 @@
+pub mod export;
+pub mod view;
+
+pub use export::export;
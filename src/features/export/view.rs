@@ -0,0 +1,33 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    Target {
+        name: String,
+        resource_type: String,
+        frame_name: String,
+    },
+    Error(String),
+}
+
+impl Renderable for View {
+    fn render(&self) -> String {
+        match self {
+            View::Target {
+                name,
+                resource_type,
+                frame_name,
+            } => format!(
+                "{} target `{}`: {} from frame `{}`\n",
+                "Exporting".indent().bold().cyan(),
+                &name,
+                &resource_type,
+                &frame_name,
+            ),
+            View::Error(description) => {
+                format!("{} {}\n", "Error".indent().bold().red(), &description)
+            }
+        }
+    }
+}
@@ -1,18 +1,30 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use crate::api::figma::FigmaApi;
 use crate::common::error::AppError;
-use crate::common::fetching::{fetch, FetcherTarget};
-use crate::common::fileutils::{create_dir, move_file};
+use crate::common::fetching::{fetch, fetch_node, FetcherEntry, FetcherTarget};
+use crate::common::fileutils::{
+    create_temp_dir, unchanged_content, DebugFiles, StagedFiles, TEMP_DIR_PATH,
+};
 use crate::common::gathering::gathering::gather_names;
-use crate::common::http_client::create_http_client;
+use crate::common::raster::diff::compare_images;
+use crate::common::raster::{render_svg_scaled, render_vector_drawable};
 use crate::common::renderer::Renderer;
-use crate::common::res_name::to_res_name;
+use crate::common::res_name::{given_res_name, validate_res_names};
+use crate::common::vdtool::error::VectorDrawableError;
 use crate::common::vdtool::vdtool::convert_svg_to_xml;
 use crate::feature_icons::view::View;
-use crate::models::config::{AppConfig, IconFormat, ImageFormat};
+use crate::models::config::{describe_routes, AppConfig, IconFormat, ImageFormat, ResourceType};
+use crate::models::lockfile::{lock_exported, LockedResource, Lockfile};
+
+/// Icons are rendered in higher resolution to notice small details during verification
+const VERIFY_RENDER_SCALE: f32 = 4f32;
 
 #[derive(Debug, Clone)]
 struct IconInfo {
     id: String,
+    /// Figma file with the icon
+    file_id: String,
     user_name: String,
     format: IconFormat,
     res: ResourceInfo,
@@ -32,61 +44,235 @@ impl IconInfo {
             "drawable".to_string()
         }
     }
+
+    /// Icon will be written to drawable dir of android project with the other ones
+    fn output_path(&self, app_config: &AppConfig) -> String {
+        let res_path = &app_config
+            .icon_main_res(&self.res.name)
+            .0
+            .expect("Validation is done in fetcher");
+        format!(
+            "{}/drawable/{}.{}",
+            &res_path,
+            &self.res.name,
+            &self.format.extension()
+        )
+    }
 }
 
-pub fn export_icons(token: &String, image_names: &Vec<String>, yaml_config_path: &String) {
+pub fn export_icons(
+    token: &Option<String>,
+    image_names: &[String],
+    url: &Option<String>,
+    yaml_config_path: &String,
+    verify: bool,
+    keep_temp_files: bool,
+    allow_partial: bool,
+) {
     let renderer = Renderer();
-    let api = FigmaApi::new(create_http_client(&token));
+    let api = match FigmaApi::from_token(token) {
+        Ok(api) => api,
+        Err(e) => {
+            renderer.new_line();
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
 
-    let fetcher_entry = match fetch(&api, &yaml_config_path, FetcherTarget::Icons, &renderer) {
+    let fetch_result = match url {
+        Some(url) => fetch_node(&api, yaml_config_path, url, image_names, &renderer),
+        None => fetch(
+            &api,
+            yaml_config_path,
+            FetcherTarget::Icons,
+            image_names,
+            &renderer,
+        ),
+    };
+    let fetcher_entry = match fetch_result {
         Ok(fetcher_entry) => fetcher_entry,
         Err(e) => {
             renderer.render(View::Error(format!("{}", e)));
             return;
         }
     };
-    let (app_config, names_to_ids) = (fetcher_entry.app_config, fetcher_entry.image_names_to_ids);
+    // The node from the link is exported with the name chosen by the fetcher
+    let image_names: Vec<String> = match url {
+        Some(_) => fetcher_entry.image_names_to_ids.keys().cloned().collect(),
+        None => image_names.to_vec(),
+    };
+    if let Err(e) = export_fetched_icons(
+        &api,
+        fetcher_entry,
+        &image_names,
+        yaml_config_path,
+        verify,
+        keep_temp_files,
+        allow_partial,
+    ) {
+        renderer.render(View::Error(e.to_string()));
+    }
+}
 
-    let frame_name = &app_config.common.icons.figma_frame_name;
+/// Export icons found by the fetcher. It is used for every target of `fxa export` too.
+pub fn export_fetched_icons(
+    api: &FigmaApi,
+    fetcher_entry: FetcherEntry,
+    image_names: &[String],
+    yaml_config_path: &String,
+    verify: bool,
+    keep_temp_files: bool,
+    allow_partial: bool,
+) -> Result<(), AppError> {
+    let renderer = Renderer();
+    let debug_files = DebugFiles::new(keep_temp_files);
+    let (app_config, names_to_ids, names_to_files, file_versions, config_hash) = (
+        fetcher_entry.app_config,
+        fetcher_entry.image_names_to_ids,
+        fetcher_entry.image_names_to_files,
+        fetcher_entry.file_versions,
+        fetcher_entry.config_hash,
+    );
+    let node_url = fetcher_entry.node_url;
+    let target = fetcher_entry.target;
+    let given_res_names = fetcher_entry.image_names_to_res_names;
+    let mut lockfile = Lockfile::from_file(yaml_config_path)?;
+
+    let frame_name = app_config.frame_names(&ResourceType::Icons);
+    let naming = &app_config.common.icons.naming;
     let format = &app_config.android.icons.format;
     let icons_for_export: Vec<IconInfo> = gather_names(
         &app_config,
         &frame_name,
-        &image_names,
+        image_names,
         &names_to_ids,
         true,
         |e| IconInfo {
             id: e.figma_id,
+            file_id: names_to_files[&e.figma_name].clone(),
             user_name: e.user_name.clone(),
             format: format.clone(),
             res: ResourceInfo {
-                name: to_res_name(&e.user_name),
+                name: given_res_name(&e.user_name, &given_res_names, naming),
                 night: e.night,
             },
         },
     );
 
+    // Invalid names would fail only in the Android build, so nothing is downloaded
+    validate_res_names(
+        icons_for_export.iter().map(|e| (&e.user_name, &e.res.name)),
+        naming,
+        |error| {
+            renderer.render(View::Error(error.to_string()));
+            renderer.new_line();
+        },
+    )?;
+
+    // Tell the user where the resources go, if they are spread over several modules
+    for (res_name, main_res, route) in describe_routes(
+        &app_config.android.icons.routes,
+        icons_for_export.iter().map(|e| &e.res.name),
+        app_config.main_res_icons(),
+    ) {
+        renderer.render(View::Routed(res_name, main_res, route));
+        renderer.new_line();
+    }
+
+    let mut staged_files = StagedFiles::default();
+    let mut locked_icons: BTreeMap<String, LockedResource> = BTreeMap::new();
+    let mut unchanged_count = 0usize;
+    let mut failed_count = 0usize;
+    let mut failed_icons: BTreeSet<String> = BTreeSet::new();
     for icon in icons_for_export {
-        let export_result = export_icon(&api, &app_config, &icon, &renderer);
+        let path = icon.output_path(&app_config);
+        let file_version = file_versions.get(&icon.file_id).cloned().flatten();
+        let previously_locked = lockfile.icons.get(&icon.res.name);
+        let locked_icon = locked_icons
+            .entry(icon.res.name.clone())
+            .or_insert_with(|| LockedResource {
+                url: node_url.clone(),
+                target: target.clone(),
+                ..LockedResource::new(&icon.user_name, &file_version, &config_hash)
+            });
+
+        // Nothing has changed in Figma and in the config since the last export
+        if let Some(locked_output) = previously_locked
+            .filter(|locked| locked.is_up_to_date(&file_version, &config_hash, &path))
+            .and_then(|locked| locked.outputs.get(&path))
+        {
+            locked_icon.outputs.insert(path, locked_output.clone());
+            renderer.render(View::IconUnchanged(
+                icon.user_name.clone(),
+                icon.drawable_dir_name(),
+            ));
+            unchanged_count += 1;
+            renderer.new_line();
+            continue;
+        }
+
+        let export_result = export_icon(api, &app_config, &icon, verify, &debug_files, &renderer);
 
         match export_result {
-            Err(e) => renderer.render(View::Error(e.to_string())),
-            Ok(()) => (),
+            Ok(content) => {
+                // Don't touch the file if it is already up to date
+                if let Some(kept_content) = unchanged_content(&path, &content) {
+                    locked_icon.record(&path, &icon.id, &kept_content);
+                    renderer.render(View::IconUnchanged(
+                        icon.user_name.clone(),
+                        icon.drawable_dir_name(),
+                    ));
+                    unchanged_count += 1;
+                } else {
+                    locked_icon.record(&path, &icon.id, &content);
+                    // Tell the user that we are done
+                    renderer.render(View::IconExported(
+                        icon.user_name.clone(),
+                        icon.drawable_dir_name(),
+                    ));
+                    staged_files.stage(path, content);
+                }
+            }
+            Err(e) => {
+                renderer.render(View::Error(e.to_string()));
+                failed_icons.insert(icon.res.name.clone());
+                failed_count += 1;
+            }
         }
 
         renderer.new_line();
     }
 
-    renderer.render(View::Done { message: None });
+    // Write all exported icons to the project at once
+    if failed_count > 0 && !allow_partial {
+        return Err(AppError::ExportAborted(failed_count));
+    }
+    // Remember what is exported, so unchanged icons are skipped next time
+    let replaced_paths = lock_exported(&mut lockfile.icons, locked_icons, &failed_icons);
+    let files_count = staged_files.len();
+    for path in replaced_paths {
+        staged_files.stage_removal(path);
+    }
+    staged_files.commit()?;
+    lockfile.save(yaml_config_path)?;
+    renderer.render(View::Done {
+        message: Some(format!(
+            "{} files are written to the project, {} files are unchanged",
+            files_count, unchanged_count
+        )),
+    });
+    Ok(())
 }
 
 fn export_icon(
     api: &FigmaApi,
     app_config: &AppConfig,
     icon: &IconInfo,
+    verify: bool,
+    debug_files: &DebugFiles,
     renderer: &Renderer,
-) -> Result<(), AppError> {
-    let file_id = &app_config.figma.file_id;
+) -> Result<Vec<u8>, AppError> {
+    let file_id = &icon.file_id;
     let node_id = &icon.id;
 
     // Get download url for exported icon
@@ -97,68 +283,128 @@ fn export_icon(
     let icon_download_url =
         api.get_image_download_url(file_id, node_id, 1.0f32, &ImageFormat::Svg)?;
 
-    // Download icon from gotten url to app's TEMPORARY dir
+    // Download icon from gotten url to memory
     renderer.render(View::DownloadingIcon(
         icon.user_name.clone(),
         icon.drawable_dir_name(),
     ));
-    let icon_temporary_file_name = api.get_image(
-        &icon_download_url,
-        &icon.res.name,
-        &icon.drawable_dir_name(),
-        &ImageFormat::Svg,
-    )?;
+    let svg_content = api.get_image(&icon_download_url)?;
+    let debug_file_prefix = format!("{}_{}", &icon.res.name, icon.drawable_dir_name());
+    debug_files.save(&format!("{}_figma.svg", debug_file_prefix), &svg_content)?;
+    let svg_content = String::from_utf8(svg_content).map_err(|e| {
+        AppError::CannotConvertToXml(VectorDrawableError::CannotParseSvg(
+            icon.user_name.clone(),
+            e.to_string(),
+        ))
+    })?;
 
     // Convert to VectorDrawable XML
-    let icon_temporary_file_name =
-        convert_to_vector_drawable(&icon, &icon_temporary_file_name, &renderer)?;
-
-    // Create drawable dir in res dir of android project
-    renderer.render(View::IconDownloaded(
-        icon.user_name.clone(),
-        icon.drawable_dir_name(),
-    ));
-    let res_path = &app_config
-        .main_res_icons()
-        .expect("Validation is done in fetcher");
-    let full_final_icon_dir = format!("{}/drawable", &res_path);
-    create_dir(&full_final_icon_dir)
-        .map_err(|e| AppError::CannotCreateDrawableDir(format!("{}", e)))?;
-
-    // Move icon from temporary dir to drawable dir of android project
+    let icon_content = convert_to_vector_drawable(icon, &svg_content, renderer)?;
     let extension = icon.format.extension();
-    let full_final_icon_path =
-        format!("{}/{}.{}", full_final_icon_dir, &icon.res.name, &extension,);
-    move_file(&icon_temporary_file_name, &full_final_icon_path)
-        .map_err(|e| AppError::CannotMoveToDrawableDir(icon.user_name.clone(), format!("{}", e)))?;
+    debug_files.save(
+        &format!("{}.{}", debug_file_prefix, &extension),
+        &icon_content,
+    )?;
+
+    // Compare the converted icon with the original one
+    if verify && matches!(icon.format, IconFormat::Xml) {
+        verify_vector_drawable(app_config, icon, &svg_content, &icon_content, renderer)?;
+    }
 
-    // Tell the user that we are done
-    renderer.render(View::IconExported(
+    renderer.render(View::IconDownloaded(
         icon.user_name.clone(),
         icon.drawable_dir_name(),
     ));
-    Ok(())
+    Ok(icon_content)
 }
 
 fn convert_to_vector_drawable(
     icon: &IconInfo,
-    icon_file_name: &String,
+    svg_content: &str,
     renderer: &Renderer,
-) -> Result<String, AppError> {
+) -> Result<Vec<u8>, AppError> {
     match icon.format {
         IconFormat::Xml => {
             renderer.render(View::ConvertingToXml(
                 icon.user_name.clone(),
                 icon.drawable_dir_name(),
             ));
-            let new_icon_path =
-                convert_svg_to_xml(icon_file_name).map_err(AppError::CannotConvertToXml)?;
+            let xml_content = convert_svg_to_xml(&icon.user_name, svg_content)
+                .map_err(AppError::CannotConvertToXml)?;
             renderer.render(View::ConvertedToXml(
                 icon.user_name.clone(),
                 icon.drawable_dir_name(),
             ));
-            Ok(new_icon_path)
+            Ok(xml_content)
         }
-        IconFormat::Svg => Ok(icon_file_name.clone()),
+        IconFormat::Svg => Ok(svg_content.as_bytes().to_vec()),
+    }
+}
+
+fn verify_vector_drawable(
+    app_config: &AppConfig,
+    icon: &IconInfo,
+    svg_content: &str,
+    xml_content: &[u8],
+    renderer: &Renderer,
+) -> Result<(), AppError> {
+    renderer.render(View::VerifyingIcon(
+        icon.user_name.clone(),
+        icon.drawable_dir_name(),
+    ));
+    let verification_error = |e: String| AppError::CannotVerifyIcon(icon.user_name.clone(), e);
+
+    let xml_content = String::from_utf8_lossy(xml_content);
+    let expected =
+        render_svg_scaled(svg_content, VERIFY_RENDER_SCALE).map_err(verification_error)?;
+    let (width, height) = expected.dimensions();
+    let actual = render_vector_drawable(&xml_content, width, height).map_err(verification_error)?;
+    let diff = compare_images(&expected, &actual)
+        .ok_or_else(|| verification_error("rendered images have different sizes".to_string()))?;
+
+    let options = &app_config.android.icons.verify_options;
+    if diff.difference <= options.threshold {
+        renderer.render(View::IconVerified(
+            icon.user_name.clone(),
+            icon.drawable_dir_name(),
+            diff.difference,
+        ));
+        return Ok(());
+    }
+
+    // Save renders and the difference to the temporary dir for investigation
+    create_temp_dir().map_err(|_| AppError::CreateTempDir)?;
+    let diff_file_prefix = format!(
+        "{}/{}_{}",
+        TEMP_DIR_PATH,
+        &icon.res.name,
+        icon.drawable_dir_name()
+    );
+    let diff_file_name = format!("{}_diff.png", diff_file_prefix);
+    for (image, file_name) in [
+        (&expected, format!("{}_expected.png", diff_file_prefix)),
+        (&actual, format!("{}_actual.png", diff_file_prefix)),
+        (&diff.image, diff_file_name.clone()),
+    ] {
+        image
+            .save(&file_name)
+            .map_err(|e| verification_error(e.to_string()))?;
+    }
+
+    if options.fail_on_mismatch {
+        Err(AppError::IconMismatch(
+            icon.user_name.clone(),
+            diff.difference,
+            options.threshold,
+            diff_file_name,
+        ))
+    } else {
+        renderer.render(View::IconMismatch(
+            icon.user_name.clone(),
+            icon.drawable_dir_name(),
+            diff.difference,
+            diff_file_name,
+        ));
+        Ok(())
     }
 }
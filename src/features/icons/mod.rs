@@ -1,4 +1,5 @@
 pub mod icons;
 pub mod view;
 
+pub use icons::export_fetched_icons;
 pub use icons::export_icons;
@@ -8,7 +8,12 @@ pub enum View {
     IconDownloaded(String, String),
     ConvertingToXml(String, String),
     ConvertedToXml(String, String),
+    VerifyingIcon(String, String),
+    IconVerified(String, String, f32),
+    IconMismatch(String, String, f32, String),
     IconExported(String, String),
+    IconUnchanged(String, String),
+    Routed(String, String, Option<String>),
     Error(String),
     Done { message: Option<String> },
 }
@@ -54,6 +59,33 @@ impl Renderable for View {
                     &dir_name,
                 )
             }
+            View::VerifyingIcon(image_name, dir_name) => {
+                format!(
+                    "{} rendered Android Drawable XML image {} ({})",
+                    "Verifying".indent().bold().cyan(),
+                    &image_name,
+                    &dir_name,
+                )
+            }
+            View::IconVerified(image_name, dir_name, difference) => {
+                format!(
+                    "{} rendered Android Drawable XML image {} ({}), difference {:.2}%",
+                    "Verified".indent().bold().green(),
+                    &image_name,
+                    &dir_name,
+                    difference,
+                )
+            }
+            View::IconMismatch(image_name, dir_name, difference, diff_file_name) => {
+                format!(
+                    "{} rendered Android Drawable XML image {} ({}) differs from SVG by {:.2}%, see {}\n",
+                    "Warning".indent().bold().yellow(),
+                    &image_name,
+                    &dir_name,
+                    difference,
+                    &diff_file_name,
+                )
+            }
             View::IconExported(image_name, dir_name) => {
                 format!(
                     "{} icon {} ({})",
@@ -62,6 +94,19 @@ impl Renderable for View {
                     &dir_name,
                 )
             }
+            View::IconUnchanged(image_name, dir_name) => format!(
+                "{} icon {} ({})",
+                "Unchanged".indent().bold().green(),
+                &image_name,
+                &dir_name,
+            ),
+            View::Routed(res_name, main_res, route) => format!(
+                "{} {} to {} ({})",
+                "Routed".indent().bold().green(),
+                &res_name,
+                &main_res,
+                route.as_deref().unwrap_or("fallback"),
+            ),
             View::Error(description) => {
                 format!("{} {}", "Error".indent().bold().red(), &description)
             }
@@ -1,18 +1,50 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Cursor;
+
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageOutputFormat, RgbaImage};
+
 use crate::api::figma::FigmaApi;
 use crate::common::error::AppError;
-use crate::common::fetching::{fetch, FetcherTarget};
-use crate::common::fileutils::{create_dir, move_file};
+use crate::common::fetching::{fetch, fetch_node, FetcherEntry, FetcherTarget};
+use crate::common::fileutils::{unchanged_content, DebugFiles, StagedFiles};
 use crate::common::gathering::gathering::gather_names;
-use crate::common::http_client::create_http_client;
+use crate::common::raster::{render_svg_scaled, unsupported_svg_features};
 use crate::common::renderer::Renderer;
-use crate::common::res_name::to_res_name;
+use crate::common::res_name::{given_res_name, validate_res_names};
 use crate::common::webp;
 use crate::feature_images::view::View;
-use crate::models::config::{AppConfig, ImageFormat};
+use crate::models::config::{
+    describe_routes, AndroidImagesWebpConfig, AppConfig, ImageFormat, ImageRenderMode, ResourceType,
+};
+use crate::models::figma::Rectangle;
+use crate::models::lockfile::{lock_exported, LockedResource, Lockfile};
+
+/// Sources which are downloaded once per Figma node and shared between all scales of the image.
+/// Nodes are identified by their names, because nodes of different files may have the same ids.
+#[derive(Default)]
+struct SharedSources {
+    /// SVG sources for local rendering.
+    /// `None` means that the image can't be rendered locally and Figma should render it.
+    svg: HashMap<String, Option<String>>,
+    /// Renders of the largest scale for downscaling
+    largest_renders: HashMap<String, RgbaImage>,
+}
+
+/// Image rendered for the desired scale, before encoding to the final format.
+enum RenderedImage {
+    /// PNG or SVG file rendered by Figma
+    Encoded(Vec<u8>),
+    /// Image rendered or resampled locally
+    Decoded(RgbaImage),
+}
 
 #[derive(Debug, Clone)]
 struct ImageInfo {
     id: String,
+    /// Figma file with the image
+    file_id: String,
+    figma_name: String,
     user_name: String,
     format: ImageFormat,
     res: ResourceInfo,
@@ -40,36 +72,117 @@ impl ImageInfo {
             (false, false) => format!("drawable-{}", self.res.scale.name),
         }
     }
+
+    /// Image will be written to drawable-XXXX dir of android project with the other ones
+    fn output_path(&self, app_config: &AppConfig) -> String {
+        let res_dir = &app_config
+            .image_main_res(&self.res.name)
+            .0
+            .expect("Validation is done in fetcher");
+        format!(
+            "{}/{}/{}.{}",
+            &res_dir,
+            self.drawable_dir_name(),
+            &self.res.name,
+            &self.format.extension()
+        )
+    }
 }
 
-pub fn export_images(token: &String, image_names: &[String], yaml_config_path: &String) {
+pub fn export_images(
+    token: &Option<String>,
+    image_names: &[String],
+    url: &Option<String>,
+    yaml_config_path: &String,
+    keep_temp_files: bool,
+    allow_partial: bool,
+) {
     let renderer = Renderer();
-    let api = FigmaApi::new(create_http_client(&token));
+    let api = match FigmaApi::from_token(token) {
+        Ok(api) => api,
+        Err(e) => {
+            renderer.new_line();
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
 
-    let fetcher_entry = match fetch(&api, &yaml_config_path, FetcherTarget::Images, &renderer) {
+    let fetch_result = match url {
+        Some(url) => fetch_node(&api, yaml_config_path, url, image_names, &renderer),
+        None => fetch(
+            &api,
+            yaml_config_path,
+            FetcherTarget::Images,
+            image_names,
+            &renderer,
+        ),
+    };
+    let fetcher_entry = match fetch_result {
         Ok(fetcher_entry) => fetcher_entry,
         Err(e) => {
             renderer.render(View::Error(format!("{}", e)));
             return;
         }
     };
-    let (app_config, names_to_ids) = (fetcher_entry.app_config, fetcher_entry.image_names_to_ids);
+    // The node from the link is exported with the name chosen by the fetcher
+    let image_names: Vec<String> = match url {
+        Some(_) => fetcher_entry.image_names_to_ids.keys().cloned().collect(),
+        None => image_names.to_vec(),
+    };
+    if let Err(e) = export_fetched_images(
+        &api,
+        fetcher_entry,
+        &image_names,
+        yaml_config_path,
+        keep_temp_files,
+        allow_partial,
+    ) {
+        renderer.render(View::Error(e.to_string()));
+    }
+}
 
-    let frame_name = &app_config.common.images.figma_frame_name;
+/// Export images found by the fetcher. It is used for every target of `fxa export` too.
+pub fn export_fetched_images(
+    api: &FigmaApi,
+    fetcher_entry: FetcherEntry,
+    image_names: &[String],
+    yaml_config_path: &String,
+    keep_temp_files: bool,
+    allow_partial: bool,
+) -> Result<(), AppError> {
+    let renderer = Renderer();
+    let debug_files = DebugFiles::new(keep_temp_files);
+    let (app_config, names_to_ids, names_to_files, names_to_bounds, file_versions, config_hash) = (
+        fetcher_entry.app_config,
+        fetcher_entry.image_names_to_ids,
+        fetcher_entry.image_names_to_files,
+        fetcher_entry.image_names_to_bounds,
+        fetcher_entry.file_versions,
+        fetcher_entry.config_hash,
+    );
+    let node_url = fetcher_entry.node_url;
+    let target = fetcher_entry.target;
+    let given_res_names = fetcher_entry.image_names_to_res_names;
+    let mut lockfile = Lockfile::from_file(yaml_config_path)?;
+
+    let frame_name = app_config.frame_names(&ResourceType::Images);
+    let naming = &app_config.common.images.naming;
     let format = &app_config.android.images.format;
     let single_scale_format = format.is_svg();
     let images_for_export: Vec<ImageInfo> = gather_names(
         &app_config,
         &frame_name,
-        &image_names,
+        image_names,
         &names_to_ids,
         single_scale_format,
         |e| ImageInfo {
             id: e.figma_id,
+            file_id: names_to_files[&e.figma_name].clone(),
+            figma_name: e.figma_name.clone(),
             user_name: e.user_name.clone(),
             format: format.clone(),
             res: ResourceInfo {
-                name: to_res_name(&e.user_name),
+                name: given_res_name(&e.user_name, &given_res_names, naming),
                 scale: ScaleInfo {
                     name: e.scale_name.clone(),
                     value: e.scale_value,
@@ -79,28 +192,180 @@ pub fn export_images(token: &String, image_names: &[String], yaml_config_path: &
         },
     );
 
+    // Invalid names would fail only in the Android build, so nothing is downloaded
+    validate_res_names(
+        images_for_export
+            .iter()
+            .map(|e| (&e.user_name, &e.res.name)),
+        naming,
+        |error| {
+            renderer.render(View::Error(error.to_string()));
+            renderer.new_line();
+        },
+    )?;
+
+    // Tell the user where the resources go, if they are spread over several modules
+    for (res_name, main_res, route) in describe_routes(
+        &app_config.android.images.routes,
+        images_for_export.iter().map(|e| &e.res.name),
+        app_config.main_res_images(),
+    ) {
+        renderer.render(View::Routed(res_name, main_res, route));
+        renderer.new_line();
+    }
+
+    let mut shared_sources = SharedSources::default();
+    let mut staged_files = StagedFiles::default();
+    let mut locked_images: BTreeMap<String, LockedResource> = BTreeMap::new();
+    let mut unchanged_count = 0usize;
+    let mut failed_count = 0usize;
+    let mut failed_images: BTreeSet<String> = BTreeSet::new();
     for image in images_for_export {
-        let export_result = export_image(&api, &app_config, &image, &renderer);
+        let path = image.output_path(&app_config);
+        let file_version = file_versions.get(&image.file_id).cloned().flatten();
+        let previously_locked = lockfile.images.get(&image.res.name);
+        let locked_image = locked_images
+            .entry(image.res.name.clone())
+            .or_insert_with(|| LockedResource {
+                url: node_url.clone(),
+                target: target.clone(),
+                ..LockedResource::new(&image.user_name, &file_version, &config_hash)
+            });
+
+        // Nothing has changed in Figma and in the config since the last export
+        if let Some(locked_output) = previously_locked
+            .filter(|locked| locked.is_up_to_date(&file_version, &config_hash, &path))
+            .and_then(|locked| locked.outputs.get(&path))
+        {
+            locked_image.outputs.insert(path, locked_output.clone());
+            renderer.render(View::ImageUnchanged(
+                image.user_name.clone(),
+                image.drawable_dir_name(),
+            ));
+            unchanged_count += 1;
+            renderer.new_line();
+            continue;
+        }
+
+        let export_result = export_image(
+            api,
+            &app_config,
+            &image,
+            names_to_bounds.get(&image.figma_name),
+            &mut shared_sources,
+            &debug_files,
+            &renderer,
+        );
 
-        match &export_result {
-            Err(e) => renderer.render(View::Error(e.to_string())),
-            Ok(()) => (),
+        match export_result {
+            Ok(content) => {
+                // Don't touch the file if it is already up to date
+                if let Some(kept_content) = unchanged_content(&path, &content) {
+                    locked_image.record(&path, &image.id, &kept_content);
+                    renderer.render(View::ImageUnchanged(
+                        image.user_name.clone(),
+                        image.drawable_dir_name(),
+                    ));
+                    unchanged_count += 1;
+                } else {
+                    locked_image.record(&path, &image.id, &content);
+                    // Tell the user that we are done exporting image for this scale
+                    renderer.render(View::ImageExported(
+                        image.user_name.clone(),
+                        image.drawable_dir_name(),
+                    ));
+                    staged_files.stage(path, content);
+                }
+            }
+            Err(e) => {
+                renderer.render(View::Error(e.to_string()));
+                failed_images.insert(image.res.name.clone());
+                failed_count += 1;
+            }
         }
 
         renderer.new_line();
     }
 
-    renderer.render(View::Done { message: None });
+    // Write all exported images to the project at once
+    if failed_count > 0 && !allow_partial {
+        return Err(AppError::ExportAborted(failed_count));
+    }
+    // Remember what is exported, so unchanged images are skipped next time
+    let replaced_paths = lock_exported(&mut lockfile.images, locked_images, &failed_images);
+    let files_count = staged_files.len();
+    for path in replaced_paths {
+        staged_files.stage_removal(path);
+    }
+    staged_files.commit()?;
+    lockfile.save(yaml_config_path)?;
+    renderer.render(View::Done {
+        message: Some(format!(
+            "{} files are written to the project, {} files are unchanged",
+            files_count, unchanged_count
+        )),
+    });
+    Ok(())
 }
 
 fn export_image(
     api: &FigmaApi,
     app_config: &AppConfig,
     image: &ImageInfo,
+    bounds: Option<&Rectangle>,
+    shared_sources: &mut SharedSources,
+    debug_files: &DebugFiles,
     renderer: &Renderer,
-) -> Result<(), AppError> {
-    let file_id = &app_config.figma.file_id;
-    let quality = app_config.android.images.webp_options.quality;
+) -> Result<Vec<u8>, AppError> {
+    let webp_options = app_config.image_webp_options(&image.res.name);
+
+    // Render image locally or let Figma render it
+    let render_mode = match image.format {
+        ImageFormat::Svg => &ImageRenderMode::Figma,
+        _ => app_config.image_render_mode(&image.res.name),
+    };
+    let rendered_image = match render_mode {
+        ImageRenderMode::Figma => download_rendered(api, image, debug_files, renderer)?,
+        ImageRenderMode::Local => {
+            match get_svg_source(api, image, shared_sources, debug_files, renderer)? {
+                Some(svg_content) => render_locally(image, &svg_content, renderer)?,
+                None => download_rendered(api, image, debug_files, renderer)?,
+            }
+        }
+        ImageRenderMode::Downscale => downscale_largest_render(
+            api,
+            app_config,
+            image,
+            bounds,
+            shared_sources,
+            debug_files,
+            renderer,
+        )?,
+    };
+
+    // So... Convert if necessary :)
+    let image_content = encode_to_final_format(image, rendered_image, &webp_options, renderer)?;
+    let extension = image.format.extension();
+    debug_files.save(
+        &format!(
+            "{}_{}.{}",
+            &image.res.name,
+            image.drawable_dir_name(),
+            &extension
+        ),
+        &image_content,
+    )?;
+
+    Ok(image_content)
+}
+
+fn download_rendered(
+    api: &FigmaApi,
+    image: &ImageInfo,
+    debug_files: &DebugFiles,
+    renderer: &Renderer,
+) -> Result<RenderedImage, AppError> {
+    let file_id = &image.file_id;
     let node_id = &image.id;
 
     // Get download url for exported image
@@ -111,67 +376,215 @@ fn export_image(
     let image_download_url =
         api.get_image_download_url(file_id, node_id, image.res.scale.value, &image.format)?;
 
-    // Download image from gotten url to app's TEMPORARY dir
+    // Download image from gotten url to memory
     renderer.render(View::DownloadingImage(
         image.user_name.clone(),
         image.drawable_dir_name(),
     ));
-    let image_format = &app_config.android.images.format;
-    let image_temporary_file_name = api.get_image(
-        &image_download_url,
-        &image.res.name,
-        &image.res.scale.name,
-        &image_format,
+    let image_content = api.get_image(&image_download_url)?;
+    let download_extension = match image.format {
+        ImageFormat::Svg => "svg",
+        _ => "png",
+    };
+    debug_files.save(
+        &format!(
+            "{}_{}_figma.{}",
+            &image.res.name,
+            image.drawable_dir_name(),
+            download_extension
+        ),
+        &image_content,
     )?;
+    Ok(RenderedImage::Encoded(image_content))
+}
 
-    // So... Convert if necessary :)
-    let image_temporary_file_name =
-        convert_to_webp_if_necessary(&image, image_temporary_file_name, quality, &renderer)?;
-
-    // Create drawable-XXXX dir in res dir of android project
-    let res_dir = &app_config
-        .main_res_images()
-        .expect("Validation is done in fetcher");
-    let full_final_image_dir = format!("{}/{}", &res_dir, image.drawable_dir_name());
-    create_dir(&full_final_image_dir)
-        .map_err(|e| AppError::CannotCreateDrawableDir(e.to_string()))?;
-
-    // Move image from temporary dir to drawable dir of android project
-    let extension = image.format.extension();
-    let full_final_image_path = format!(
-        "{}/{}.{}",
-        full_final_image_dir, &image.res.name, &extension
-    );
-    move_file(&image_temporary_file_name, &full_final_image_path)
-        .map_err(|e| AppError::CannotMoveToDrawableDir(image.user_name.clone(), e.to_string()))?;
+/// Download SVG source of the image only once for all scales.
+/// Returns `None` if the image uses features which can't be rendered locally.
+fn get_svg_source(
+    api: &FigmaApi,
+    image: &ImageInfo,
+    shared_sources: &mut SharedSources,
+    debug_files: &DebugFiles,
+    renderer: &Renderer,
+) -> Result<Option<String>, AppError> {
+    if let Some(local_source) = shared_sources.svg.get(&image.figma_name) {
+        return Ok(local_source.clone());
+    }
+
+    renderer.render(View::DownloadingSource(image.user_name.clone()));
+    let file_id = &image.file_id;
+    let source_download_url =
+        api.get_image_download_url(file_id, &image.id, 1f32, &ImageFormat::Svg)?;
+    let source_content = api.get_image(&source_download_url)?;
+    debug_files.save(&format!("{}_source.svg", &image.res.name), &source_content)?;
+    let svg_content = String::from_utf8(source_content)
+        .map_err(|e| AppError::CannotRenderImage(image.user_name.clone(), e.to_string()))?;
 
-    // Tell the user that we are done exporting image for this scale
-    renderer.render(View::ImageExported(
+    let unsupported_features = unsupported_svg_features(&svg_content)
+        .map_err(|e| AppError::CannotRenderImage(image.user_name.clone(), e))?;
+    let local_source = if unsupported_features.is_empty() {
+        renderer.render(View::SourceDownloaded(image.user_name.clone()));
+        Some(svg_content)
+    } else {
+        renderer.render(View::LocalRenderingUnsupported(
+            image.user_name.clone(),
+            unsupported_features.join(", "),
+        ));
+        None
+    };
+    shared_sources
+        .svg
+        .insert(image.figma_name.clone(), local_source.clone());
+    Ok(local_source)
+}
+
+/// Render image for the desired scale from SVG source.
+fn render_locally(
+    image: &ImageInfo,
+    svg_content: &str,
+    renderer: &Renderer,
+) -> Result<RenderedImage, AppError> {
+    renderer.render(View::RenderingImage(
         image.user_name.clone(),
         image.drawable_dir_name(),
     ));
-    Ok(())
+    render_svg_scaled(svg_content, image.res.scale.value)
+        .map(RenderedImage::Decoded)
+        .map_err(|e| AppError::CannotRenderImage(image.user_name.clone(), e))
 }
 
-fn convert_to_webp_if_necessary(
+/// Download the render of the largest scale only once for all scales,
+/// then resample it to the size of the desired scale.
+fn downscale_largest_render(
+    api: &FigmaApi,
+    app_config: &AppConfig,
+    image: &ImageInfo,
+    bounds: Option<&Rectangle>,
+    shared_sources: &mut SharedSources,
+    debug_files: &DebugFiles,
+    renderer: &Renderer,
+) -> Result<RenderedImage, AppError> {
+    let largest_scale = app_config
+        .android
+        .images
+        .scales
+        .values()
+        .fold(image.res.scale.value, |a, &b| a.max(b));
+
+    if !shared_sources
+        .largest_renders
+        .contains_key(&image.figma_name)
+    {
+        renderer.render(View::DownloadingLargestRender(
+            image.user_name.clone(),
+            largest_scale,
+        ));
+        let file_id = &image.file_id;
+        let download_url =
+            api.get_image_download_url(file_id, &image.id, largest_scale, &ImageFormat::Png)?;
+        let largest_render_content = api.get_image(&download_url)?;
+        debug_files.save(
+            &format!("{}_largest.png", &image.res.name),
+            &largest_render_content,
+        )?;
+        let largest_render = image::load_from_memory(&largest_render_content)
+            .map_err(|e| AppError::CannotDecode(image.user_name.clone(), e.to_string()))?
+            .into_rgba8();
+        shared_sources
+            .largest_renders
+            .insert(image.figma_name.clone(), largest_render);
+    }
+    let largest_render = &shared_sources.largest_renders[&image.figma_name];
+
+    renderer.render(View::DownscalingImage(
+        image.user_name.clone(),
+        image.drawable_dir_name(),
+    ));
+    let (width, height) = downscaled_size(
+        bounds,
+        largest_render.dimensions(),
+        largest_scale,
+        image.res.scale.value,
+    );
+    let downscaled_image = if (width, height) == largest_render.dimensions() {
+        largest_render.clone()
+    } else {
+        image::imageops::resize(largest_render, width, height, FilterType::Lanczos3)
+    };
+    Ok(RenderedImage::Decoded(downscaled_image))
+}
+
+/// Returns the size of the image for the scale.
+///
+/// Base size is taken from the node's render bounds, so pixel sizes match the Figma renders exactly,
+/// shadows and other effects included. Documents cached by older versions of the app have no bounds,
+/// so the base size is restored from the largest render.
+fn downscaled_size(
+    bounds: Option<&Rectangle>,
+    largest_size: (u32, u32),
+    largest_scale: f32,
+    scale: f32,
+) -> (u32, u32) {
+    let (base_width, base_height) = match bounds {
+        Some(bounds) => (bounds.width, bounds.height),
+        None => (
+            largest_size.0 as f32 / largest_scale,
+            largest_size.1 as f32 / largest_scale,
+        ),
+    };
+    let width = ((base_width * scale).round() as u32).max(1);
+    let height = ((base_height * scale).round() as u32).max(1);
+    (width, height)
+}
+
+/// Encode rendered image to the desired format, converting it to WEBP if necessary.
+fn encode_to_final_format(
     image: &ImageInfo,
-    image_file_name: String,
-    quality: f32,
+    rendered_image: RenderedImage,
+    webp_options: &AndroidImagesWebpConfig,
     renderer: &Renderer,
-) -> Result<String, AppError> {
-    match image.format {
-        ImageFormat::Webp => {
+) -> Result<Vec<u8>, AppError> {
+    match (&image.format, rendered_image) {
+        (ImageFormat::Webp, rendered_image) => {
             renderer.render(View::ConvertingToWebp(
                 image.user_name.clone(),
                 image.drawable_dir_name(),
             ));
-            let new_image_path = webp::image_to_webp(&image_file_name, quality)?;
+            let decoded_image = match rendered_image {
+                RenderedImage::Encoded(content) => image::load_from_memory(&content)
+                    .map_err(|e| AppError::CannotDecode(image.user_name.clone(), e.to_string()))?,
+                RenderedImage::Decoded(pixels) => DynamicImage::ImageRgba8(pixels),
+            };
+            let webp_content = webp::image_to_webp(&image.user_name, decoded_image, webp_options)?;
             renderer.render(View::ConvertedToWebp(
                 image.user_name.clone(),
                 image.drawable_dir_name(),
             ));
-            Ok(new_image_path)
+            Ok(webp_content)
+        }
+        (_, RenderedImage::Encoded(content)) => Ok(content),
+        (_, RenderedImage::Decoded(pixels)) => {
+            let mut content = Vec::new();
+            DynamicImage::ImageRgba8(pixels)
+                .write_to(&mut Cursor::new(&mut content), ImageOutputFormat::Png)
+                .map_err(|e| AppError::CannotRenderImage(image.user_name.clone(), e.to_string()))?;
+            Ok(content)
         }
-        _ => Ok(image_file_name),
     }
 }
+
+#[test]
+fn test_downscaled_size() {
+    let bounds = Rectangle {
+        x: 0f32,
+        y: 0f32,
+        width: 116f32,
+        height: 66f32,
+    };
+    assert_eq!(
+        downscaled_size(Some(&bounds), (464, 264), 4f32, 1.5f32),
+        (174, 99)
+    );
+    assert_eq!(downscaled_size(None, (464, 264), 4f32, 2f32), (232, 132));
+    assert_eq!(downscaled_size(None, (3, 3), 4f32, 0.1f32), (1, 1));
+}
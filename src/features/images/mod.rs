@@ -1,4 +1,5 @@
 pub mod images;
 pub mod view;
 
+pub use images::export_fetched_images;
 pub use images::export_images;
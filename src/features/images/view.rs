@@ -5,9 +5,17 @@ use crate::common::renderer::{Indentable, Renderable};
 pub enum View {
     FetchingImage(String, String),
     DownloadingImage(String, String),
+    DownloadingSource(String),
+    SourceDownloaded(String),
+    LocalRenderingUnsupported(String, String),
+    RenderingImage(String, String),
+    DownloadingLargestRender(String, f32),
+    DownscalingImage(String, String),
     ConvertingToWebp(String, String),
     ConvertedToWebp(String, String),
     ImageExported(String, String),
+    ImageUnchanged(String, String),
+    Routed(String, String, Option<String>),
     Error(String),
     Done { message: Option<String> },
 }
@@ -27,6 +35,40 @@ impl Renderable for View {
                 &image_name,
                 &scale,
             ),
+            View::DownloadingSource(image_name) => format!(
+                "{} SVG source of image {}",
+                "Downloading".indent().bold().cyan(),
+                &image_name,
+            ),
+            View::SourceDownloaded(image_name) => format!(
+                "{} SVG source of image {}, it will be rendered locally\n",
+                "Downloaded".indent().bold().green(),
+                &image_name,
+            ),
+            View::LocalRenderingUnsupported(image_name, features) => format!(
+                "{} image {} uses {}, so it will be rendered by Figma\n",
+                "Warning".indent().bold().yellow(),
+                &image_name,
+                &features,
+            ),
+            View::RenderingImage(image_name, scale) => format!(
+                "{} image {} ({})",
+                "Rendering".indent().bold().cyan(),
+                &image_name,
+                &scale,
+            ),
+            View::DownloadingLargestRender(image_name, scale) => format!(
+                "{} image {} at scale {}x",
+                "Downloading".indent().bold().cyan(),
+                &image_name,
+                &scale,
+            ),
+            View::DownscalingImage(image_name, scale) => format!(
+                "{} image {} ({})",
+                "Downscaling".indent().bold().cyan(),
+                &image_name,
+                &scale,
+            ),
             View::ConvertingToWebp(image_name, scale) => format!(
                 "{} to WEBP image {} ({})...",
                 "Converting".indent().bold().cyan(),
@@ -45,6 +87,19 @@ impl Renderable for View {
                 &image_name,
                 &scale,
             ),
+            View::ImageUnchanged(image_name, scale) => format!(
+                "{} image {} ({})",
+                "Unchanged".indent().bold().green(),
+                &image_name,
+                &scale,
+            ),
+            View::Routed(res_name, main_res, route) => format!(
+                "{} {} to {} ({})",
+                "Routed".indent().bold().green(),
+                &res_name,
+                &main_res,
+                route.as_deref().unwrap_or("fallback"),
+            ),
             View::Error(description) => {
                 format!("{} {}", "Error".indent().bold().red(), &description)
             }
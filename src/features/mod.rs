@@ -1,4 +1,12 @@
+pub mod auth;
 pub mod cleanup;
+pub mod colors;
 pub mod config;
+pub mod convert;
+pub mod export;
 pub mod icons;
 pub mod images;
+pub mod prune;
+pub mod unused;
+pub mod watch;
+pub mod webhook;
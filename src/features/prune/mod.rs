@@ -0,0 +1,4 @@
+pub mod prune;
+pub mod view;
+
+pub use prune::prune;
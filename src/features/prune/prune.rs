@@ -0,0 +1,415 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::stdin;
+use std::path::Path;
+
+use crate::api::figma::FigmaApi;
+use crate::common::error::AppError;
+use crate::common::fetching::{
+    fetch, fetch_sources, FetchedDocuments, FetcherEntry, FetcherTarget,
+};
+use crate::common::renderer::Renderer;
+use crate::common::res_name::given_res_name;
+use crate::common::suffixes::SuffixExt;
+use crate::feature_export::export::config_for_target;
+use crate::feature_prune::view::View;
+use crate::models::config::{AppConfig, NamingConfig, ResourceType};
+use crate::models::lockfile::{has_frame_resources, LockedOutput, LockedResource, Lockfile};
+
+/// Delete resources which were exported earlier, but whose Figma nodes are deleted or renamed.
+///
+/// Only files recorded in `fxa.lock` are considered, so resources added to the project
+/// by hand are never touched, and resources exported with `--url` are kept. Resources exported
+/// by the targets of `fxa export` are looked for in the frames of their targets.
+/// Figma documents are always loaded fresh, not from the cache.
+pub fn prune(token: &Option<String>, yaml_config_path: &String, yes: bool) {
+    let renderer = Renderer();
+    let api = match FigmaApi::from_token(token) {
+        Ok(api) => api,
+        Err(e) => {
+            renderer.new_line();
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+
+    let mut lockfile = match Lockfile::from_file(yaml_config_path) {
+        Ok(lockfile) => lockfile,
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+
+    // Nodes deleted in Figma after the document was cached must not be taken for live ones
+    let app_config = match AppConfig::from_file(yaml_config_path) {
+        Ok(app_config) => app_config,
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+    for file_id in &app_config.file_ids() {
+        if let Err(e) = api.invalidate_document(file_id) {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    }
+
+    let mut stale_paths: BTreeSet<String> = BTreeSet::new();
+    for (fetcher_target, locked_resources) in [
+        (FetcherTarget::Images, &mut lockfile.images),
+        (FetcherTarget::Icons, &mut lockfile.icons),
+    ] {
+        if !has_frame_resources(locked_resources) {
+            continue;
+        }
+        let fetcher_entry = match fetch(&api, yaml_config_path, fetcher_target, &[], &renderer) {
+            Ok(fetcher_entry) => fetcher_entry,
+            Err(e) => {
+                renderer.render(View::Error(e.to_string()));
+                return;
+            }
+        };
+        let live_res_names = live_res_names_of(&fetcher_entry, fetcher_target);
+        stale_paths.extend(remove_stale_outputs(
+            locked_resources,
+            None,
+            &live_res_names,
+        ));
+    }
+
+    // Resources of the targets are looked for in the frames of their targets. Resources of
+    // the targets which are removed from the config can't be checked
+    let mut documents = FetchedDocuments::new();
+    for target in &app_config.targets {
+        let (fetcher_target, locked_resources) = match target.resource_type {
+            ResourceType::Images => (FetcherTarget::Images, &mut lockfile.images),
+            ResourceType::Icons => (FetcherTarget::Icons, &mut lockfile.icons),
+            // Colors are written to one file, which is rewritten entirely by every export
+            ResourceType::Colors => continue,
+        };
+        if !locked_resources
+            .values()
+            .any(|resource| resource.target.as_ref() == Some(&target.name))
+        {
+            continue;
+        }
+        let fetcher_entry =
+            config_for_target(&app_config, target, yaml_config_path).and_then(|target_config| {
+                fetch_sources(
+                    &api,
+                    target_config,
+                    String::new(),
+                    fetcher_target,
+                    &[],
+                    &mut documents,
+                    &renderer,
+                )
+            });
+        let fetcher_entry = match fetcher_entry {
+            Ok(fetcher_entry) => fetcher_entry,
+            Err(e) => {
+                renderer.render(View::Error(e.to_string()));
+                return;
+            }
+        };
+        let live_res_names = live_res_names_of(&fetcher_entry, fetcher_target);
+        stale_paths.extend(remove_stale_outputs(
+            locked_resources,
+            Some(&target.name),
+            &live_res_names,
+        ));
+    }
+
+    // Files which are already deleted by hand are just forgotten
+    let stale_files: Vec<String> = stale_paths
+        .into_iter()
+        .filter(|path| Path::new(path).is_file())
+        .collect();
+    for path in &stale_files {
+        renderer.render(View::StaleFile(path.clone()));
+    }
+
+    delete_files(&stale_files, &lockfile, yaml_config_path, yes, &renderer);
+}
+
+/// Res names which can be exported from each node of the fetched frames, by node ids.
+fn live_res_names_of(
+    fetcher_entry: &FetcherEntry,
+    fetcher_target: FetcherTarget,
+) -> HashMap<String, HashSet<String>> {
+    let common = &fetcher_entry.app_config.common;
+    let naming = match fetcher_target {
+        FetcherTarget::Images => &common.images.naming,
+        FetcherTarget::Icons => &common.icons.naming,
+        FetcherTarget::Colors => &common.colors.naming,
+    };
+    collect_live_res_names(
+        &fetcher_entry.image_names_to_ids,
+        &fetcher_entry.image_names_to_res_names,
+        naming,
+    )
+}
+
+/// Res names which can be exported from each node of the frame, by node ids.
+/// Themed nodes (`name_light`, `name_dark`) are exported both with and without the suffix.
+fn collect_live_res_names(
+    names_to_ids: &HashMap<String, String>,
+    given_res_names: &HashMap<String, String>,
+    naming: &NamingConfig,
+) -> HashMap<String, HashSet<String>> {
+    let mut live_res_names: HashMap<String, HashSet<String>> = HashMap::new();
+    for (name, id) in names_to_ids {
+        let res_names = live_res_names.entry(id.clone()).or_default();
+        res_names.insert(given_res_name(name, given_res_names, naming));
+        res_names.insert(given_res_name(
+            &name.without_theme_suffix(),
+            given_res_names,
+            naming,
+        ));
+    }
+    live_res_names
+}
+
+/// Remove outputs whose nodes are deleted or renamed from the locked resources which are
+/// exported from the frames of the `target`, or from the main frames if it is `None`.
+/// Returns paths of the stale files, which are always the paths recorded in the lockfile.
+fn remove_stale_outputs(
+    locked_resources: &mut BTreeMap<String, LockedResource>,
+    target: Option<&String>,
+    live_res_names: &HashMap<String, HashSet<String>>,
+) -> BTreeSet<String> {
+    let mut stale_paths: BTreeSet<String> = BTreeSet::new();
+    // Nodes exported with `--url` are not in the frames, so they can't be checked
+    for (res_name, resource) in locked_resources
+        .iter_mut()
+        .filter(|(_, resource)| resource.url.is_none() && resource.target.as_ref() == target)
+    {
+        let is_live = |output: &LockedOutput| {
+            live_res_names
+                .get(&output.node_id)
+                .is_some_and(|names| names.contains(res_name))
+        };
+        let (live_outputs, stale_outputs): (BTreeMap<_, _>, BTreeMap<_, _>) =
+            std::mem::take(&mut resource.outputs)
+                .into_iter()
+                .partition(|(_, output)| is_live(output));
+        resource.outputs = live_outputs;
+        stale_paths.extend(stale_outputs.into_keys());
+    }
+    locked_resources.retain(|_, resource| !resource.outputs.is_empty());
+    stale_paths
+}
+
+/// Delete the files after confirmation and save the lockfile, from which they are already removed.
+///
+/// The confirmation is skipped if `yes` is `true` or there is nothing to delete.
+pub fn delete_files(
+    files: &[String],
+    lockfile: &Lockfile,
+    yaml_config_path: &String,
+    yes: bool,
+    renderer: &Renderer,
+) {
+    if !files.is_empty() && !yes {
+        renderer.render(View::Confirmation(files.len()));
+        let mut answer = String::new();
+        stdin().read_line(&mut answer).unwrap_or_default();
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            renderer.render(View::Cancelled);
+            return;
+        }
+    }
+
+    let mut failed_count = 0usize;
+    for path in files {
+        match fs::remove_file(path) {
+            Ok(()) => renderer.render(View::FileDeleted(path.clone())),
+            Err(e) => {
+                renderer.render(View::Error(
+                    AppError::CannotDeleteStaleFile(path.clone(), e.to_string()).to_string(),
+                ));
+                renderer.new_line();
+                failed_count += 1;
+            }
+        }
+    }
+
+    // Keep the lockfile as is, so the files which can't be deleted are found next time
+    if failed_count > 0 {
+        return;
+    }
+    if let Err(e) = lockfile.save(yaml_config_path) {
+        renderer.render(View::Error(e.to_string()));
+        return;
+    }
+    renderer.render(View::Done {
+        message: Some(format!("{} files are deleted", files.len())),
+    });
+}
+
+#[test]
+fn test_remove_stale_outputs() {
+    let mut locked_resources: BTreeMap<String, LockedResource> = BTreeMap::new();
+    for (res_name, node_ids) in [
+        ("ic_kept", vec!["1:1"]),
+        ("ic_renamed", vec!["1:2"]),
+        ("ic_deleted", vec!["1:3"]),
+        ("img_themed", vec!["1:4", "1:5"]),
+    ] {
+        let mut resource = LockedResource::new(res_name, &Some("1".to_string()), "hash");
+        for (i, node_id) in node_ids.iter().enumerate() {
+            let path = format!("/nonexistent/res/drawable-{}/{}.xml", i, res_name);
+            resource.record(&path, node_id, b"");
+        }
+        locked_resources.insert(res_name.to_string(), resource);
+    }
+    let names_to_ids: HashMap<String, String> = [
+        ("ic_kept", "1:1"),
+        ("ic_new_name", "1:2"),
+        ("img_themed_light", "1:4"),
+    ]
+    .iter()
+    .map(|(name, id)| (name.to_string(), id.to_string()))
+    .collect();
+
+    let stale_paths = remove_stale_outputs(
+        &mut locked_resources,
+        None,
+        &collect_live_res_names(&names_to_ids, &HashMap::new(), &NamingConfig::default()),
+    );
+
+    assert_eq!(
+        stale_paths.into_iter().collect::<Vec<String>>(),
+        vec![
+            "/nonexistent/res/drawable-0/ic_deleted.xml",
+            "/nonexistent/res/drawable-0/ic_renamed.xml",
+            "/nonexistent/res/drawable-1/img_themed.xml",
+        ]
+    );
+    assert_eq!(
+        locked_resources.keys().collect::<Vec<&String>>(),
+        vec!["ic_kept", "img_themed"]
+    );
+}
+
+#[test]
+fn test_url_resources_are_not_stale() {
+    let mut frame_resource = LockedResource::new("ic_deleted", &Some("1".to_string()), "hash");
+    frame_resource.record("/nonexistent/res/drawable/ic_deleted.xml", "1:1", b"");
+    let mut url_resource = LockedResource {
+        url: Some("https://www.figma.com/file/KEY/Name?node-id=5-6".to_string()),
+        ..LockedResource::new("ic_promo", &Some("1".to_string()), "hash")
+    };
+    url_resource.record("/nonexistent/res/drawable/ic_promo.xml", "5:6", b"");
+    let lockfile = Lockfile {
+        icons: BTreeMap::from([
+            ("ic_deleted".to_string(), frame_resource),
+            ("ic_promo".to_string(), url_resource),
+        ]),
+        ..Lockfile::default()
+    };
+    // The lockfile is read back as it is saved after `fxa icons --url`
+    let mut lockfile: Lockfile =
+        serde_yaml::from_str(&serde_yaml::to_string(&lockfile).unwrap()).unwrap();
+
+    let stale_paths = remove_stale_outputs(&mut lockfile.icons, None, &HashMap::new());
+
+    assert_eq!(
+        stale_paths.into_iter().collect::<Vec<String>>(),
+        vec!["/nonexistent/res/drawable/ic_deleted.xml"]
+    );
+    assert_eq!(
+        lockfile.icons.keys().collect::<Vec<&String>>(),
+        vec!["ic_promo"]
+    );
+    assert!(has_frame_resources(&BTreeMap::from([(
+        "ic_deleted".to_string(),
+        LockedResource::new("ic_deleted", &None, "hash"),
+    )])));
+    assert!(!has_frame_resources(&lockfile.icons));
+}
+
+#[test]
+fn test_hand_added_files_are_not_stale() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = |dir_name: &str| {
+        let drawable_dir = dir.path().join(dir_name);
+        fs::create_dir_all(&drawable_dir).unwrap();
+        let path = drawable_dir.join("img_deleted.webp");
+        fs::write(&path, b"webp").unwrap();
+        path.to_string_lossy().to_string()
+    };
+    let recorded_path = path("drawable-hdpi");
+    // Added to the project by hand, with the same name
+    let hand_added_path = path("drawable-night-hdpi");
+    let mut resource = LockedResource::new("img_deleted", &Some("1".to_string()), "hash");
+    resource.record(&recorded_path, "1:1", b"webp");
+    let mut locked_resources = BTreeMap::from([("img_deleted".to_string(), resource)]);
+
+    let stale_paths = remove_stale_outputs(&mut locked_resources, None, &HashMap::new());
+
+    assert_eq!(
+        stale_paths.into_iter().collect::<Vec<String>>(),
+        vec![recorded_path]
+    );
+    assert!(Path::new(&hand_added_path).is_file());
+    assert!(locked_resources.is_empty());
+}
+
+#[test]
+fn test_target_resources_are_checked_in_their_frames() {
+    let mut frame_resource = LockedResource::new("ic_deleted", &Some("1".to_string()), "hash");
+    frame_resource.record("/nonexistent/res/drawable/ic_deleted.xml", "1:1", b"");
+    let target_resource = |name: &str, node_id: &str| {
+        let mut resource = LockedResource {
+            target: Some("payments-icons".to_string()),
+            ..LockedResource::new(name, &Some("1".to_string()), "hash")
+        };
+        let path = format!("/nonexistent/payments/res/drawable/{}.xml", name);
+        resource.record(&path, node_id, b"");
+        resource
+    };
+    let lockfile = Lockfile {
+        icons: BTreeMap::from([
+            ("ic_deleted".to_string(), frame_resource),
+            ("ic_card".to_string(), target_resource("ic_card", "7:1")),
+            ("ic_wallet".to_string(), target_resource("ic_wallet", "7:2")),
+        ]),
+        ..Lockfile::default()
+    };
+    // The lockfile is read back as it is saved after `fxa export`
+    let mut lockfile: Lockfile =
+        serde_yaml::from_str(&serde_yaml::to_string(&lockfile).unwrap()).unwrap();
+    let live_res_names = |names_to_ids: &[(&str, &str)]| {
+        let names_to_ids: HashMap<String, String> = names_to_ids
+            .iter()
+            .map(|(name, id)| (name.to_string(), id.to_string()))
+            .collect();
+        collect_live_res_names(&names_to_ids, &HashMap::new(), &NamingConfig::default())
+    };
+
+    // Target resources are not in the main frame, but they are not stale
+    let stale_paths = remove_stale_outputs(&mut lockfile.icons, None, &live_res_names(&[]));
+    assert_eq!(
+        stale_paths.into_iter().collect::<Vec<String>>(),
+        vec!["/nonexistent/res/drawable/ic_deleted.xml"]
+    );
+    assert!(!has_frame_resources(&lockfile.icons));
+
+    let target = "payments-icons".to_string();
+    let stale_paths = remove_stale_outputs(
+        &mut lockfile.icons,
+        Some(&target),
+        &live_res_names(&[("ic_card", "7:1")]),
+    );
+    assert_eq!(
+        stale_paths.into_iter().collect::<Vec<String>>(),
+        vec!["/nonexistent/payments/res/drawable/ic_wallet.xml"]
+    );
+    assert_eq!(
+        lockfile.icons.keys().collect::<Vec<&String>>(),
+        vec!["ic_card"]
+    );
+}
@@ -0,0 +1,48 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    StaleFile(String),
+    Confirmation(usize),
+    Cancelled,
+    FileDeleted(String),
+    Error(String),
+    Done { message: Option<String> },
+}
+
+impl Renderable for View {
+    fn render(&self) -> String {
+        match self {
+            View::StaleFile(path) => format!(
+                "{} {} is no longer in Figma\n",
+                "Stale".indent().bold().yellow(),
+                &path,
+            ),
+            View::Confirmation(count) => format!(
+                "{} {} files? [y/N]",
+                "Delete".indent().bold().cyan(),
+                &count,
+            ),
+            View::Cancelled => {
+                format!(
+                    "{} nothing is deleted",
+                    "Cancelled".indent().bold().yellow()
+                )
+            }
+            View::FileDeleted(path) => {
+                format!("{} {}\n", "Deleted".indent().bold().green(), &path)
+            }
+            View::Error(description) => {
+                format!("{} {}", "Error".indent().bold().red(), &description)
+            }
+            View::Done { message } => {
+                if let Some(m) = message {
+                    format!("{} {}", "Done".indent().bold().green(), &m)
+                } else {
+                    format!("{}", "Done".indent().bold().green())
+                }
+            }
+        }
+    }
+}
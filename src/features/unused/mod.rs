@@ -0,0 +1,4 @@
+pub mod unused;
+pub mod view;
+
+pub use unused::find_unused;
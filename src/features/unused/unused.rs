@@ -0,0 +1,155 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::common::renderer::Renderer;
+use crate::feature_prune::prune::delete_files;
+use crate::feature_unused::view::View;
+use crate::models::lockfile::{LockedResource, Lockfile};
+
+/// Extensions of the files which can reference drawables
+const SOURCE_EXTENSIONS: [&str; 3] = ["kt", "java", "xml"];
+
+/// Dirs with generated files, dependencies and caches, which are never scanned
+const IGNORED_DIRS: [&str; 2] = ["build", "node_modules"];
+
+/// Prefixes of the drawable references in the sources:
+/// `R.drawable.x` in Kotlin and Java, including `painterResource(R.drawable.x)`,
+/// and `@drawable/x` in XML
+const REFERENCE_PREFIXES: [&str; 2] = ["R.drawable.", "@drawable/"];
+
+/// Report exported resources from `fxa.lock` which are never referenced in the project sources.
+///
+/// # Arguments
+///
+/// * `yaml_config_path` - config, next to which the lockfile is saved
+/// * `project_dir` - root dir of the Android project to scan
+/// * `prune` - delete the files of unused resources
+/// * `yes` - delete the files without confirmation
+pub fn find_unused(yaml_config_path: &String, project_dir: &String, prune: bool, yes: bool) {
+    let renderer = Renderer();
+    renderer.new_line();
+
+    let mut lockfile = match Lockfile::from_file(yaml_config_path) {
+        Ok(lockfile) => lockfile,
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+
+    renderer.render(View::Scanning(project_dir.clone()));
+    let mut references: HashSet<String> = HashSet::new();
+    let files_count = scan_dir(Path::new(project_dir), &mut references);
+    renderer.render(View::Scanned(files_count, references.len()));
+
+    let mut unused_files: Vec<String> = Vec::new();
+    let mut unused_count = 0usize;
+    for locked_resources in [&mut lockfile.images, &mut lockfile.icons] {
+        for (res_name, resource) in remove_unused(locked_resources, &references) {
+            renderer.render(View::UnusedResource(res_name, resource.outputs.len()));
+            unused_files.extend(
+                resource
+                    .outputs
+                    .into_keys()
+                    .filter(|path| Path::new(path).is_file()),
+            );
+            unused_count += 1;
+        }
+    }
+
+    if prune {
+        delete_files(&unused_files, &lockfile, yaml_config_path, yes, &renderer);
+    } else {
+        renderer.render(View::Done {
+            message: Some(format!(
+                "{} exported resources are unused. Add `--prune` flag to delete them",
+                unused_count
+            )),
+        });
+    }
+}
+
+/// Recursively collect drawable references from the source files of the dir.
+/// Returns the count of scanned files.
+fn scan_dir(dir: &Path, references: &mut HashSet<String>) -> usize {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    let mut files_count = 0usize;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            // Hidden dirs are `.git`, `.gradle`, `.idea`, `.fxa` and so on
+            if !name.starts_with('.') && !IGNORED_DIRS.contains(&name.as_str()) {
+                files_count += scan_dir(&path, references);
+            }
+            continue;
+        }
+        let is_source = path
+            .extension()
+            .is_some_and(|e| SOURCE_EXTENSIONS.contains(&e.to_string_lossy().as_ref()));
+        if !is_source {
+            continue;
+        }
+        // Binary or non UTF-8 files can't contain references
+        if let Ok(content) = fs::read_to_string(&path) {
+            collect_references(&content, references);
+            files_count += 1;
+        }
+    }
+    files_count
+}
+
+/// Find names of all drawables which are referenced in the content.
+fn collect_references(content: &str, references: &mut HashSet<String>) {
+    for prefix in REFERENCE_PREFIXES {
+        for (start, _) in content.match_indices(prefix) {
+            let name: String = content[start + prefix.len()..]
+                .chars()
+                .take_while(|ch| ch.is_ascii_alphanumeric() || *ch == '_')
+                .collect();
+            if !name.is_empty() {
+                references.insert(name);
+            }
+        }
+    }
+}
+
+/// Remove resources which are not referenced from the locked ones. Returns the removed resources.
+fn remove_unused(
+    locked_resources: &mut BTreeMap<String, LockedResource>,
+    references: &HashSet<String>,
+) -> BTreeMap<String, LockedResource> {
+    let (used, unused) = std::mem::take(locked_resources)
+        .into_iter()
+        .partition(|(res_name, _)| references.contains(res_name));
+    *locked_resources = used;
+    unused
+}
+
+#[test]
+fn test_collect_references() {
+    let mut references: HashSet<String> = HashSet::new();
+    collect_references(
+        r#"
+        import com.example.R
+        val icon = painterResource(R.drawable.ic_24_search)
+        imageView.setImageResource(R.drawable.img_banner)
+        val color = R.color.primary
+        "#,
+        &mut references,
+    );
+    collect_references(
+        r#"<ImageView android:src="@drawable/ic_16_close" android:background="@color/white" />"#,
+        &mut references,
+    );
+    let mut references: Vec<String> = references.into_iter().collect();
+    references.sort();
+    assert_eq!(
+        references,
+        vec!["ic_16_close", "ic_24_search", "img_banner"]
+    );
+}
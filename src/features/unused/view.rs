@@ -0,0 +1,45 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    Scanning(String),
+    Scanned(usize, usize),
+    UnusedResource(String, usize),
+    Error(String),
+    Done { message: Option<String> },
+}
+
+impl Renderable for View {
+    fn render(&self) -> String {
+        match self {
+            View::Scanning(project_dir) => format!(
+                "{} sources in {}",
+                "Scanning".indent().bold().cyan(),
+                &project_dir,
+            ),
+            View::Scanned(files_count, references_count) => format!(
+                "{} {} source files, found {} drawable references\n",
+                "Scanned".indent().bold().green(),
+                &files_count,
+                &references_count,
+            ),
+            View::UnusedResource(res_name, files_count) => format!(
+                "{} resource {} is never referenced ({} files)\n",
+                "Unused".indent().bold().yellow(),
+                &res_name,
+                &files_count,
+            ),
+            View::Error(description) => {
+                format!("{} {}", "Error".indent().bold().red(), &description)
+            }
+            View::Done { message } => {
+                if let Some(m) = message {
+                    format!("{} {}", "Done".indent().bold().green(), &m)
+                } else {
+                    format!("{}", "Done".indent().bold().green())
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod view;
+pub mod watch;
+
+pub use watch::watch;
@@ -0,0 +1,60 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    Watching(String, u64),
+    CheckingVersion(String),
+    VersionChanged(Option<String>, String),
+    NothingToSync,
+    ResourceChanged(String),
+    NothingChanged,
+    Error(String),
+}
+
+impl Renderable for View {
+    fn render(&self) -> String {
+        match self {
+            View::Watching(file_id, interval) => format!(
+                "{} figma file {}, checking for changes every {}s. Press Ctrl+C to stop",
+                "Watching".indent().bold().cyan(),
+                &file_id,
+                &interval,
+            ),
+            View::CheckingVersion(file_id) => format!(
+                "{} version of figma file {}",
+                "Checking".indent().bold().cyan(),
+                &file_id,
+            ),
+            View::VersionChanged(old_version, new_version) => match old_version {
+                Some(old_version) => format!(
+                    "{} figma file version {} -> {}, syncing resources\n",
+                    "Changed".indent().bold().green(),
+                    &old_version,
+                    &new_version,
+                ),
+                None => format!(
+                    "{} figma file version {}, syncing resources\n",
+                    "Found".indent().bold().green(),
+                    &new_version,
+                ),
+            },
+            View::NothingToSync => format!(
+                "{} there are no resources exported from the frames in fxa.lock yet and no targets in the file, export them with `fxa images` or `fxa icons` first\n",
+                "Warning".indent().bold().yellow(),
+            ),
+            View::ResourceChanged(res_name) => format!(
+                "{} resource {}\n",
+                "Changed".indent().bold().green(),
+                &res_name,
+            ),
+            View::NothingChanged => format!(
+                "{} resources are up to date\n",
+                "Unchanged".indent().bold().green(),
+            ),
+            View::Error(description) => {
+                format!("{} {}\n", "Error".indent().bold().red(), &description)
+            }
+        }
+    }
+}
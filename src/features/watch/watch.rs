@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, HashMap};
+use std::thread::sleep;
+use std::time::Duration;
+
+use crate::api::figma::FigmaApi;
+use crate::common::error::AppError;
+use crate::common::fetching::{fetch, read_targets_config, FetcherTarget};
+use crate::common::gathering::gathering::all_names;
+use crate::common::renderer::Renderer;
+use crate::feature_colors::export_fetched_colors;
+use crate::feature_export::export::{export_targets, ExportFlags};
+use crate::feature_icons::export_fetched_icons;
+use crate::feature_images::export_fetched_images;
+use crate::feature_watch::view::View;
+use crate::models::config::{AppConfig, TargetConfig};
+use crate::models::lockfile::{has_frame_resources, LockedResource, Lockfile};
+
+/// Poll the versions of Figma files of the config and re-export resources of the configured frames
+/// and targets when it changes.
+///
+/// Only the version is requested on every poll, the whole document is loaded only after changes.
+/// Resources which haven't changed in Figma are skipped thanks to the lockfile. If an export fails,
+/// it is tried again on the next poll.
+///
+/// # Arguments
+///
+/// * `token` - Figma personal access token
+/// * `yaml_config_path` - config, next to which the lockfile is saved
+/// * `interval` - interval between polls in seconds, at least 1
+pub fn watch(token: &Option<String>, yaml_config_path: &String, interval: u64) {
+    let renderer = Renderer();
+    let api = match FigmaApi::from_token(token) {
+        Ok(api) => api,
+        Err(e) => {
+            renderer.new_line();
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+    renderer.new_line();
+
+    let file_ids = match AppConfig::from_file(yaml_config_path) {
+        Ok(app_config) => app_config.file_ids(),
+        Err(e) => {
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+    if file_ids.is_empty() {
+        let error = AppError::AppConfigMissingFileId(yaml_config_path.clone());
+        renderer.render(View::Error(error.to_string()));
+        return;
+    }
+    for file_id in &file_ids {
+        renderer.render(View::Watching(file_id.clone(), interval));
+        renderer.new_line();
+    }
+
+    let mut synced_versions: HashMap<String, String> = HashMap::new();
+    loop {
+        for file_id in &file_ids {
+            renderer.render(View::CheckingVersion(file_id.clone()));
+            match api.get_file_version(file_id) {
+                Ok(version) if synced_versions.get(file_id) != Some(&version) => {
+                    renderer.render(View::VersionChanged(
+                        synced_versions.get(file_id).cloned(),
+                        version.clone(),
+                    ));
+                    match sync(&api, file_id, yaml_config_path, &renderer) {
+                        Ok(_) => {
+                            synced_versions.insert(file_id.clone(), version);
+                        }
+                        Err(e) => renderer.render(View::Error(e.to_string())),
+                    }
+                }
+                Ok(_) => (),
+                // Network errors are not fatal, let's try again later
+                Err(e) => renderer.render(View::Error(e.to_string())),
+            }
+        }
+        sleep(Duration::from_secs(interval));
+    }
+}
+
+/// Re-export all images and icons of the configured frames, and the targets whose frames are
+/// in the file, like `fxa export` does, and tell the user which resources changed.
+/// Only the types of resources which are already exported from the frames are synced,
+/// resources exported with `--url` are not.
+/// Returns res names of the changed resources, or the error if any export has failed.
+pub fn sync(
+    api: &FigmaApi,
+    file_id: &String,
+    yaml_config_path: &String,
+    renderer: &Renderer,
+) -> Result<Vec<String>, AppError> {
+    let locked_before = Lockfile::from_file(yaml_config_path)?;
+    let sync_images = has_frame_resources(&locked_before.images);
+    let sync_icons = has_frame_resources(&locked_before.icons);
+    let (app_config, config_hash) = read_targets_config(yaml_config_path, renderer)?;
+    let targets: Vec<TargetConfig> = app_config
+        .targets
+        .iter()
+        .filter(|target| &app_config.target_file_id(target) == file_id)
+        .cloned()
+        .collect();
+    if !sync_images && !sync_icons && targets.is_empty() {
+        renderer.render(View::NothingToSync);
+        return Ok(vec![]);
+    }
+
+    // The cached document is outdated
+    api.invalidate_document(file_id)?;
+    if sync_images {
+        export_frames(api, yaml_config_path, FetcherTarget::Images, renderer)?;
+        renderer.new_line();
+    }
+    if sync_icons {
+        export_frames(api, yaml_config_path, FetcherTarget::Icons, renderer)?;
+        renderer.new_line();
+    }
+    if !targets.is_empty() {
+        let flags = ExportFlags {
+            verify: false,
+            keep_temp_files: false,
+            allow_partial: false,
+        };
+        export_targets(
+            api,
+            &app_config,
+            &config_hash,
+            &targets,
+            flags,
+            yaml_config_path,
+        )?;
+        renderer.new_line();
+    }
+
+    let locked_after = Lockfile::from_file(yaml_config_path)?;
+    let changed: Vec<String> = changed_resources(&locked_before.images, &locked_after.images)
+        .into_iter()
+        .chain(changed_resources(&locked_before.icons, &locked_after.icons))
+        .cloned()
+        .collect();
+    if changed.is_empty() {
+        renderer.render(View::NothingChanged);
+    }
+    for res_name in &changed {
+        renderer.render(View::ResourceChanged(res_name.clone()));
+    }
+    Ok(changed)
+}
+
+/// Export every resource of the configured frames, so resources added in Figma are picked up too.
+fn export_frames(
+    api: &FigmaApi,
+    yaml_config_path: &String,
+    fetcher_target: FetcherTarget,
+    renderer: &Renderer,
+) -> Result<(), AppError> {
+    let fetcher_entry = fetch(api, yaml_config_path, fetcher_target, &[], renderer)?;
+    let names = all_names(&fetcher_entry.image_names_to_ids);
+    match fetcher_target {
+        FetcherTarget::Images => {
+            export_fetched_images(api, fetcher_entry, &names, yaml_config_path, false, false)
+        }
+        FetcherTarget::Icons => export_fetched_icons(
+            api,
+            fetcher_entry,
+            &names,
+            yaml_config_path,
+            false,
+            false,
+            false,
+        ),
+        FetcherTarget::Colors => export_fetched_colors(fetcher_entry, &names, false),
+    }
+}
+
+/// Res names of the resources whose files have changed after the export.
+fn changed_resources<'a>(
+    before: &BTreeMap<String, LockedResource>,
+    after: &'a BTreeMap<String, LockedResource>,
+) -> Vec<&'a String> {
+    after
+        .iter()
+        .filter(|(res_name, resource)| {
+            let hashes = |r: &LockedResource| {
+                r.outputs
+                    .iter()
+                    .map(|(path, output)| (path.clone(), output.hash.clone()))
+                    .collect::<Vec<_>>()
+            };
+            match before.get(*res_name) {
+                Some(locked) => hashes(locked) != hashes(resource),
+                None => true,
+            }
+        })
+        .map(|(res_name, _)| res_name)
+        .collect()
+}
+
+#[test]
+fn test_changed_resources() {
+    let locked = |content: &[u8]| {
+        let mut resource = LockedResource::new("Icon", &Some("1".to_string()), "hash");
+        resource.record("./res/drawable/ic.xml", "1:1", content);
+        resource
+    };
+    let before: BTreeMap<String, LockedResource> = [
+        ("ic_same".to_string(), locked(b"same")),
+        ("ic_changed".to_string(), locked(b"old")),
+    ]
+    .into_iter()
+    .collect();
+    let after: BTreeMap<String, LockedResource> = [
+        ("ic_same".to_string(), locked(b"same")),
+        ("ic_changed".to_string(), locked(b"new")),
+        ("ic_added".to_string(), locked(b"new")),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(
+        changed_resources(&before, &after),
+        vec!["ic_added", "ic_changed"]
+    );
+}
@@ -0,0 +1,4 @@
+pub mod view;
+pub mod webhook;
+
+pub use webhook::serve_webhook;
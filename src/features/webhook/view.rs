@@ -0,0 +1,55 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    Listening(u16),
+    Received(String, String),
+    Ignored(String),
+    Rejected(String),
+    Syncing(String),
+    Synced(String, usize),
+    Error(String),
+}
+
+impl Renderable for View {
+    fn render(&self) -> String {
+        match self {
+            View::Listening(port) => format!(
+                "{} for Figma webhooks on port {}. Press Ctrl+C to stop\n",
+                "Listening".indent().bold().cyan(),
+                &port,
+            ),
+            View::Received(event_type, description) => format!(
+                "{} {} webhook: {}\n",
+                "Received".indent().bold().green(),
+                &event_type,
+                &description,
+            ),
+            View::Ignored(description) => format!(
+                "{} webhook: {}\n",
+                "Ignored".indent().bold().cyan(),
+                &description,
+            ),
+            View::Rejected(description) => format!(
+                "{} webhook: {}\n",
+                "Rejected".indent().bold().yellow(),
+                &description,
+            ),
+            View::Syncing(path) => format!(
+                "{} resources from config {}\n",
+                "Syncing".indent().bold().cyan(),
+                &path,
+            ),
+            View::Synced(path, changed_count) => format!(
+                "{} resources from config {}, {} resources changed\n",
+                "Synced".indent().bold().green(),
+                &path,
+                &changed_count,
+            ),
+            View::Error(description) => {
+                format!("{} {}\n", "Error".indent().bold().red(), &description)
+            }
+        }
+    }
+}
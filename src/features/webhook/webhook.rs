@@ -0,0 +1,418 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::api::figma::FigmaApi;
+use crate::common::error::AppError;
+use crate::common::fileutils::write_file_atomically;
+use crate::common::renderer::Renderer;
+use crate::feature_watch::watch::sync;
+use crate::feature_webhook::view::View;
+use crate::models::config::AppConfig;
+
+/// Requests with larger bodies are rejected, Figma payloads are much smaller
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Request line and headers are cut at this size, so a client can't send endless headers
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+
+/// Requests are handled one by one, so a client which stalls must not block the server
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Webhook payload sent by Figma. Only the fields used by the app are listed.
+///
+/// Details: https://www.figma.com/developers/api#webhooks-v2-payloads
+#[derive(Debug, Deserialize)]
+struct FigmaWebhookPayload {
+    event_type: String,
+    passcode: String,
+    #[serde(default)]
+    file_key: Option<String>,
+    #[serde(default)]
+    file_name: Option<String>,
+}
+
+/// HTTP request received by the server.
+struct WebhookRequest {
+    method: String,
+    body: Vec<u8>,
+}
+
+/// Decision about the received webhook.
+#[derive(Debug, PartialEq)]
+enum WebhookOutcome {
+    /// Resources from the configs must be exported again
+    Accepted {
+        event_type: String,
+        /// Id of the updated Figma file, the configs are synced with it
+        file_key: String,
+        file_name: String,
+        config_paths: Vec<String>,
+    },
+    /// Valid webhook which doesn't require export, e.g. `PING` or a webhook for another file
+    Ignored(String),
+    /// Passcode of the webhook doesn't match
+    Unauthorized,
+    /// Request is not a Figma webhook
+    BadRequest(String),
+}
+
+impl WebhookOutcome {
+    fn status_line(&self) -> &str {
+        match self {
+            WebhookOutcome::Accepted { .. } | WebhookOutcome::Ignored(_) => "200 OK",
+            WebhookOutcome::Unauthorized => "401 Unauthorized",
+            WebhookOutcome::BadRequest(_) => "400 Bad Request",
+        }
+    }
+}
+
+/// Run HTTP server which receives Figma `FILE_UPDATE` and `LIBRARY_PUBLISH` webhooks
+/// and re-exports resources of the configs with the same `figma.fileId`.
+///
+/// Requests are handled one by one. The response is sent before the export starts,
+/// so Figma doesn't retry the webhook because of a timeout.
+///
+/// # Arguments
+///
+/// * `token` - Figma personal access token
+/// * `yaml_config_paths` - configs of the projects which can be synced
+/// * `port` - port to listen on
+/// * `passcode` - passcode specified during the webhook creation
+/// * `summary_path` - file to write the summary of the last sync to
+pub fn serve_webhook(
+    token: &Option<String>,
+    yaml_config_paths: &[String],
+    port: u16,
+    passcode: &String,
+    summary_path: &Option<String>,
+) {
+    let renderer = Renderer();
+    let api = match FigmaApi::from_token(token) {
+        Ok(api) => api,
+        Err(e) => {
+            renderer.new_line();
+            renderer.render(View::Error(e.to_string()));
+            return;
+        }
+    };
+    renderer.new_line();
+
+    // Figma file ids with the paths to configs
+    let mut configs: Vec<(String, String)> = Vec::new();
+    for yaml_config_path in yaml_config_paths {
+        match AppConfig::from_file(yaml_config_path) {
+            Ok(app_config) => configs.extend(
+                app_config
+                    .file_ids()
+                    .into_iter()
+                    .map(|file_id| (file_id, yaml_config_path.clone())),
+            ),
+            Err(e) => {
+                renderer.render(View::Error(e.to_string()));
+                return;
+            }
+        }
+    }
+
+    let listener = match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            renderer.render(View::Error(
+                AppError::CannotStartWebhookServer(port, e.to_string()).to_string(),
+            ));
+            return;
+        }
+    };
+    renderer.render(View::Listening(port));
+
+    for stream in listener.incoming() {
+        let outcome = match stream {
+            Ok(stream) => receive_webhook(stream, passcode, &configs, READ_TIMEOUT),
+            Err(e) => {
+                renderer.render(View::Error(e.to_string()));
+                continue;
+            }
+        };
+
+        match outcome {
+            WebhookOutcome::Accepted {
+                event_type,
+                file_key,
+                file_name,
+                config_paths,
+            } => {
+                renderer.render(View::Received(event_type.clone(), file_name.clone()));
+                let mut summary = format!("{} of {}\n", &event_type, &file_name);
+                for yaml_config_path in config_paths {
+                    renderer.render(View::Syncing(yaml_config_path.clone()));
+                    match sync(&api, &file_key, &yaml_config_path, &renderer) {
+                        Ok(changed) => {
+                            renderer.render(View::Synced(yaml_config_path.clone(), changed.len()));
+                            summary.push_str(&format_summary(&yaml_config_path, &changed));
+                        }
+                        Err(e) => {
+                            renderer.render(View::Error(e.to_string()));
+                            summary.push_str(&format!("{}: failed, {}\n", &yaml_config_path, e));
+                        }
+                    }
+                }
+                if let Some(summary_path) = summary_path {
+                    if let Err(e) = write_file_atomically(summary_path, summary.as_bytes()) {
+                        renderer.render(View::Error(
+                            AppError::CannotWriteWebhookSummary(
+                                summary_path.clone(),
+                                e.to_string(),
+                            )
+                            .to_string(),
+                        ));
+                    }
+                }
+            }
+            WebhookOutcome::Ignored(description) => renderer.render(View::Ignored(description)),
+            WebhookOutcome::Unauthorized => {
+                renderer.render(View::Rejected("wrong passcode".to_string()))
+            }
+            WebhookOutcome::BadRequest(description) => renderer.render(View::Rejected(description)),
+        }
+    }
+}
+
+/// Read the request from the client, decide what to do with it and respond.
+/// The connection is closed before the export starts.
+fn receive_webhook(
+    mut stream: TcpStream,
+    passcode: &String,
+    configs: &[(String, String)],
+    read_timeout: Duration,
+) -> WebhookOutcome {
+    let outcome = match stream
+        .set_read_timeout(Some(read_timeout))
+        .map_err(|e| e.to_string())
+        .and_then(|_| read_request(&mut stream))
+    {
+        Ok(request) => handle_request(&request, passcode, configs),
+        Err(e) => WebhookOutcome::BadRequest(e),
+    };
+    // The client may be gone already, nothing to do about it
+    write_response(&mut stream, &outcome).unwrap_or_default();
+    outcome
+}
+
+/// Read HTTP request with the body of `Content-Length` size.
+fn read_request(stream: &mut TcpStream) -> Result<WebhookRequest, String> {
+    let mut reader = BufReader::new(stream.take(MAX_HEAD_SIZE as u64));
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .map_err(|e| e.to_string())?;
+    let method = request_line
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| "empty request".to_string())?
+        .to_string();
+
+    let mut content_length = 0usize;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).map_err(|e| e.to_string())?;
+        // Line is cut when the client is gone or the headers are too large
+        if !header.ends_with('\n') {
+            return Err("incomplete request headers".to_string());
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| "invalid Content-Length".to_string())?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        return Err("request body is too large".to_string());
+    }
+
+    // Part of the body may be read together with the headers already
+    reader.get_mut().set_limit(content_length as u64);
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    Ok(WebhookRequest { method, body })
+}
+
+fn write_response(stream: &mut TcpStream, outcome: &WebhookOutcome) -> std::io::Result<()> {
+    let body = match outcome {
+        WebhookOutcome::Accepted { .. } => "accepted".to_string(),
+        WebhookOutcome::Ignored(description) | WebhookOutcome::BadRequest(description) => {
+            description.clone()
+        }
+        WebhookOutcome::Unauthorized => "wrong passcode".to_string(),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        outcome.status_line(),
+        body.len(),
+        &body,
+    )?;
+    stream.flush()
+}
+
+/// Decide what to do with the request.
+///
+/// # Arguments
+///
+/// * `request` - received request
+/// * `passcode` - expected passcode
+/// * `configs` - Figma file ids with the paths to configs
+fn handle_request(
+    request: &WebhookRequest,
+    passcode: &String,
+    configs: &[(String, String)],
+) -> WebhookOutcome {
+    if request.method != "POST" {
+        return WebhookOutcome::BadRequest(format!("method {} is not allowed", &request.method));
+    }
+    let payload = match serde_json::from_slice::<FigmaWebhookPayload>(&request.body) {
+        Ok(payload) => payload,
+        Err(e) => return WebhookOutcome::BadRequest(format!("invalid payload: {}", e)),
+    };
+    if !constant_time_eq(payload.passcode.as_bytes(), passcode.as_bytes()) {
+        return WebhookOutcome::Unauthorized;
+    }
+    if !matches!(
+        payload.event_type.as_str(),
+        "FILE_UPDATE" | "LIBRARY_PUBLISH"
+    ) {
+        return WebhookOutcome::Ignored(format!("{} event", &payload.event_type));
+    }
+
+    let file_key = payload.file_key.unwrap_or_default();
+    let config_paths: Vec<String> = configs
+        .iter()
+        .filter(|(file_id, _)| file_id == &file_key)
+        .map(|(_, path)| path.clone())
+        .collect();
+    if config_paths.is_empty() {
+        return WebhookOutcome::Ignored(format!("no config for figma file {}", &file_key));
+    }
+    WebhookOutcome::Accepted {
+        event_type: payload.event_type,
+        file_name: payload.file_name.unwrap_or_else(|| file_key.clone()),
+        file_key,
+        config_paths,
+    }
+}
+
+/// Compare secrets in time which doesn't depend on the position of the first mismatch.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn format_summary(yaml_config_path: &String, changed: &[String]) -> String {
+    if changed.is_empty() {
+        format!("{}: resources are up to date\n", yaml_config_path)
+    } else {
+        format!(
+            "{}: {} resources changed: {}\n",
+            yaml_config_path,
+            changed.len(),
+            changed.join(", ")
+        )
+    }
+}
+
+#[test]
+fn test_receive_webhook_fixtures() {
+    let passcode = "YOUR_PASSCODE".to_string();
+    // The config exports from two files, e.g. with targets, and is synced with the updated one
+    let configs = vec![
+        ("TARGET_FILE_ID".to_string(), "fxa_config.yaml".to_string()),
+        (
+            "YOUR_FIGMA_FILE_ID".to_string(),
+            "fxa_config.yaml".to_string(),
+        ),
+    ];
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let address = listener.local_addr().unwrap();
+    let post = |body: &'static str| {
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            write!(
+                stream,
+                "POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let outcome = receive_webhook(stream, &passcode, &configs, READ_TIMEOUT);
+        (outcome, client.join().unwrap())
+    };
+
+    for fixture in [
+        include_str!("../../../res/webhook/file_update.json"),
+        include_str!("../../../res/webhook/library_publish.json"),
+    ] {
+        let (outcome, response) = post(fixture);
+        assert!(matches!(
+            outcome,
+            WebhookOutcome::Accepted { file_key, config_paths, .. }
+                if file_key == "YOUR_FIGMA_FILE_ID" && config_paths == vec!["fxa_config.yaml"]
+        ));
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+    }
+    let (outcome, _) = post(include_str!("../../../res/webhook/ping.json"));
+    assert!(matches!(outcome, WebhookOutcome::Ignored(_)));
+    let (outcome, _) = post("{}");
+    assert!(matches!(outcome, WebhookOutcome::BadRequest(_)));
+
+    let file_update = include_str!("../../../res/webhook/file_update.json");
+    let post = |body: &str| WebhookRequest {
+        method: "POST".to_string(),
+        body: body.as_bytes().to_vec(),
+    };
+    assert_eq!(
+        handle_request(&post(file_update), &"other".to_string(), &configs),
+        WebhookOutcome::Unauthorized
+    );
+    assert!(matches!(
+        handle_request(&post(file_update), &passcode, &[]),
+        WebhookOutcome::Ignored(_)
+    ));
+}
+
+#[test]
+fn test_receive_webhook_stalled_client() {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    write!(client, "POST / HTTP/1.1\r\nContent-Length: 100\r\n\r\n{{").unwrap();
+    let (stream, _) = listener.accept().unwrap();
+
+    let outcome = receive_webhook(
+        stream,
+        &"YOUR_PASSCODE".to_string(),
+        &[],
+        Duration::from_millis(100),
+    );
+
+    assert!(matches!(outcome, WebhookOutcome::BadRequest(_)));
+}
+
+#[test]
+fn test_constant_time_eq() {
+    assert!(constant_time_eq(b"passcode", b"passcode"));
+    assert!(!constant_time_eq(b"passcode", b"passcodf"));
+    assert!(!constant_time_eq(b"passcode", b"pass"));
+    assert!(!constant_time_eq(b"", b"passcode"));
+}
@@ -5,11 +5,19 @@ mod models;
 
 use clap::Parser;
 
+use crate::features::auth as feature_auth;
 use crate::features::cleanup as feature_cleanup;
+use crate::features::colors as feature_colors;
 use crate::features::config as feature_config;
+use crate::features::convert as feature_convert;
+use crate::features::export as feature_export;
 use crate::features::icons as feature_icons;
 use crate::features::images as feature_images;
-use crate::models::entrypoint::{Args, Command};
+use crate::features::prune as feature_prune;
+use crate::features::unused as feature_unused;
+use crate::features::watch as feature_watch;
+use crate::features::webhook as feature_webhook;
+use crate::models::entrypoint::{Args, AuthCommand, Command};
 
 fn main() {
     let args = Args::parse();
@@ -20,13 +28,94 @@ fn main() {
         Command::Images {
             token,
             path_to_config,
+            keep_temp_files,
+            allow_partial,
+            url,
             names,
-        } => feature_images::export_images(&token, &names, &path_to_config),
+        } => feature_images::export_images(
+            &token,
+            &names,
+            &url,
+            &path_to_config,
+            keep_temp_files,
+            allow_partial,
+        ),
         Command::Icons {
             token,
             path_to_config,
+            verify,
+            keep_temp_files,
+            allow_partial,
+            url,
             names,
-        } => feature_icons::export_icons(&token, &names, &path_to_config),
+        } => feature_icons::export_icons(
+            &token,
+            &names,
+            &url,
+            &path_to_config,
+            verify,
+            keep_temp_files,
+            allow_partial,
+        ),
+        Command::Export {
+            token,
+            path_to_config,
+            verify,
+            keep_temp_files,
+            allow_partial,
+            targets,
+        } => feature_export::export(
+            &token,
+            &path_to_config,
+            &targets,
+            verify,
+            keep_temp_files,
+            allow_partial,
+        ),
+        Command::Prune {
+            token,
+            path_to_config,
+            yes,
+        } => feature_prune::prune(&token, &path_to_config, yes),
+        Command::Unused {
+            path_to_config,
+            project_dir,
+            prune,
+            yes,
+        } => feature_unused::find_unused(&path_to_config, &project_dir, prune, yes),
+        Command::Watch {
+            token,
+            path_to_config,
+            interval,
+        } => feature_watch::watch(&token, &path_to_config, interval),
+        Command::ServeWebhook {
+            token,
+            paths_to_configs,
+            port,
+            passcode,
+            summary_path,
+        } => feature_webhook::serve_webhook(
+            &token,
+            &paths_to_configs,
+            port,
+            &passcode,
+            &summary_path,
+        ),
+        Command::Auth { subcommand } => match subcommand {
+            AuthCommand::Status { token } => feature_auth::status(&token),
+            AuthCommand::Login { token } => feature_auth::login(&token),
+            AuthCommand::Oauth {
+                client_id,
+                client_secret,
+                port,
+                scope,
+            } => feature_auth::oauth_login(&client_id, &client_secret, port, &scope),
+        },
         Command::Cleanup => feature_cleanup::cleanup(),
+        Command::Convert {
+            to,
+            output_dir,
+            files,
+        } => feature_convert::convert(&to, &output_dir, &files),
     }
 }
@@ -1,5 +1,7 @@
-use serde::Deserialize;
-use std::collections::HashMap;
+use regex::Regex;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeSet, HashMap};
 use std::fs::File;
 
 use crate::common::error::AppError;
@@ -9,11 +11,22 @@ use crate::common::error::AppError;
 /// figma:
 ///     fileId: "..."
 ///     pageName: "..."
+///     sources:
+///         - fileId: "..."
+///           pageName: "..."
+///           figmaFrameName: Icons
+///           resourceType: images | icons | colors
 /// common:
 ///     images:
 ///         figmaFrameName: Images
 ///     icons:
 ///         figmaFrameName: Icons
+///         collect:
+///             depth: 1
+///             nodeTypes: [COMPONENT, INSTANCE]
+///             variantNameTemplate: "ic_{size}_{name}"
+///     colors:
+///         figmaFrameName: Colors
 /// android:
 ///     mainRes: "./main/res"
 ///     images:
@@ -24,25 +37,96 @@ use crate::common::error::AppError;
 ///             xhdpi: 2.0
 ///             xxhdpi: 3.0
 ///         format: svg | png | webp
+///         renderMode: figma | local | downscale
 ///         webpOptions:
 ///             quality: 0..100
+///             lossless: true | false
+///             alphaQuality: 0..100
+///             method: 0..6
+///             nearLossless: 0..100
+///         overrides:
+///             img_name:
+///                 renderMode: figma | local | downscale
+///                 webpOptions:
+///                     quality: 0..100
+///         routes:
+///             - regex: "^img_(onboarding|promo)_"
+///               mainRes: "./feature-onboarding/src/main/res"
 ///     icons:
 ///         mainRes: "./main/res"
 ///         format: svg | xml
+///         routes:
+///             - prefix: ic_payments_
+///               mainRes: "./feature-payments/src/main/res"
+///     colors:
+///         mainRes: "./main/res"
+///         fileName: figma_colors.xml
+/// targets:
+///     - name: core-icons
+///       resourceType: icons
+///       figmaFrameName: Icons
+///       mainRes: "./core-ui/src/main/res"
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AppConfig {
     pub figma: FigmaConfig,
     #[serde(default = "default_common_config")]
     pub common: CommonConfig,
     pub android: AndroidConfig,
+    /// Exports which are run by `fxa export`
+    #[serde(default)]
+    pub targets: Vec<TargetConfig>,
+}
+
+/// Export of resources from one frame to one place, which is run by `fxa export`.
+/// Part of App config from YAML:
+/// ```yaml
+/// targets:
+///     - name: payments-illustrations
+///       resourceType: images | icons | colors
+///       fileId: "..."
+///       pageName: "..."
+///       figmaFrameName: Illustrations
+///       mainRes: "./feature-payments/src/main/res"
+///       format: svg | png | webp | xml
+///       names: [img_card, img_wallet]
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetConfig {
+    pub name: String,
+    pub resource_type: ResourceType,
+    /// File with the frame. Default is `figma.fileId`
+    pub file_id: Option<String>,
+    /// Page with the frame. Default is `figma.pageName`
+    pub page_name: Option<String>,
+    pub figma_frame_name: String,
+    /// Where resources are written. Default is `mainRes` of the resource type from `android`
+    pub main_res: Option<String>,
+    /// Format of resources. Default is `format` of the resource type from `android`.
+    /// Colors have no format
+    pub format: Option<TargetFormat>,
+    /// Resources to export. Empty means every resource of the frame
+    #[serde(default)]
+    pub names: Vec<String>,
+}
+
+/// Formats of both images and icons.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum TargetFormat {
+    Svg,
+    Png,
+    Webp,
+    Xml,
 }
 
 fn default_common_config() -> CommonConfig {
     CommonConfig {
         images: default_common_images_config(),
         icons: default_common_icons_config(),
+        colors: default_common_colors_config(),
     }
 }
 
@@ -51,12 +135,46 @@ fn default_common_config() -> CommonConfig {
 /// figma:
 ///     fileId: "..."
 ///     pageName: "..."
+///     sources:
+///         - fileId: "..."
+///           figmaFrameName: Icons
+///           resourceType: icons
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FigmaConfig {
+    /// File with all resources. Can be omitted if there are `sources` for every resource type
+    #[serde(default)]
+    pub file_id: String,
+    pub page_name: Option<String>,
+    /// Files and frames with resources, instead of `fileId` and `figmaFrameName` from `common`
+    #[serde(default)]
+    pub sources: Vec<FigmaSourceConfig>,
+}
+
+/// Figma frame with resources of one type. Part of App config from YAML:
+/// ```yaml
+/// sources:
+///     - fileId: "..."
+///       pageName: "..."
+///       figmaFrameName: Icons
+///       resourceType: images | icons | colors
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FigmaSourceConfig {
     pub file_id: String,
     pub page_name: Option<String>,
+    pub figma_frame_name: String,
+    pub resource_type: ResourceType,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ResourceType {
+    Images,
+    Icons,
+    Colors,
 }
 
 /// Part of App config from YAML:
@@ -66,24 +184,40 @@ pub struct FigmaConfig {
 ///         figmaFrameName: Images
 ///     icons:
 ///         figmaFrameName: Icons
+///     colors:
+///         figmaFrameName: Colors
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct CommonConfig {
     #[serde(default = "default_common_images_config")]
     pub images: CommonImagesConfig,
     #[serde(default = "default_common_icons_config")]
     pub icons: CommonIconsConfig,
+    #[serde(default = "default_common_colors_config")]
+    pub colors: CommonColorsConfig,
 }
 
 fn default_common_images_config() -> CommonImagesConfig {
     CommonImagesConfig {
         figma_frame_name: "Images".to_string(),
+        collect: default_collect_config(),
+        naming: NamingConfig::default(),
     }
 }
 
 fn default_common_icons_config() -> CommonIconsConfig {
     CommonIconsConfig {
         figma_frame_name: "Icons".to_string(),
+        collect: default_collect_config(),
+        naming: NamingConfig::default(),
+    }
+}
+
+fn default_common_colors_config() -> CommonColorsConfig {
+    CommonColorsConfig {
+        figma_frame_name: "Colors".to_string(),
+        collect: default_collect_config(),
+        naming: NamingConfig::default(),
     }
 }
 
@@ -91,22 +225,163 @@ fn default_common_icons_config() -> CommonIconsConfig {
 /// ```yaml
 /// images:
 ///     figmaFrameName: Images
+///     collect:
+///         depth: 1
+///     naming:
+///         prefix: "img_"
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CommonImagesConfig {
     pub figma_frame_name: String,
+    #[serde(default = "default_collect_config")]
+    pub collect: CollectConfig,
+    #[serde(default)]
+    pub naming: NamingConfig,
 }
 
 /// Part of App config from YAML:
 /// ```yaml
-/// images:
-///     figmaFrameName: Images
+/// icons:
+///     figmaFrameName: Icons
+///     collect:
+///         depth: 1
+///     naming:
+///         prefix: "ic_"
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CommonIconsConfig {
     pub figma_frame_name: String,
+    #[serde(default = "default_collect_config")]
+    pub collect: CollectConfig,
+    #[serde(default)]
+    pub naming: NamingConfig,
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// colors:
+///     figmaFrameName: Colors
+///     collect:
+///         depth: 2
+///     naming:
+///         prefix: "color_"
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommonColorsConfig {
+    pub figma_frame_name: String,
+    #[serde(default = "default_collect_config")]
+    pub collect: CollectConfig,
+    #[serde(default)]
+    pub naming: NamingConfig,
+}
+
+/// How resources are collected from the frame. Part of App config from YAML:
+/// ```yaml
+/// collect:
+///     depth: 1
+///     nodeTypes: [COMPONENT, INSTANCE]
+///     splitVariants: true
+///     variantNameTemplate: "ic_{size}_{name}"
+///     duplicates: fail | keepFirst | numberSuffix
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectConfig {
+    /// How deep resources are looked for. `1` means only the children of the frame,
+    /// `2` means the children of its subframes, sections and groups too, and so on
+    #[serde(default = "default_collect_depth")]
+    pub depth: u32,
+    /// Figma node types of resources, e.g. `COMPONENT`, `INSTANCE`, `FRAME`. Empty means any type
+    #[serde(default)]
+    pub node_types: Vec<String>,
+    /// Export variants of component sets as separate resources instead of the sets themselves
+    #[serde(default)]
+    pub split_variants: bool,
+    /// Name of the component set variants, e.g. `ic_{size}_{name}`, where `{set}` is the name
+    /// of the component set and the other placeholders are the variant properties
+    #[serde(default)]
+    pub variant_name_template: Option<String>,
+    /// What to do with resources which are exported under the same name
+    #[serde(default = "default_duplicates_rule")]
+    pub duplicates: DuplicatesRule,
+}
+
+/// How resources with the same name are disambiguated.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicatesRule {
+    /// The run fails with the list of duplicates
+    Fail,
+    /// The first resource in the frame is exported, the others are skipped
+    KeepFirst,
+    /// The first resource keeps its name, the others get the suffixes `_2`, `_3` and so on
+    NumberSuffix,
+}
+
+fn default_collect_config() -> CollectConfig {
+    CollectConfig {
+        depth: default_collect_depth(),
+        node_types: vec![],
+        split_variants: false,
+        variant_name_template: None,
+        duplicates: default_duplicates_rule(),
+    }
+}
+
+/// How resource names are made from Figma names. Part of App config from YAML:
+/// ```yaml
+/// naming:
+///     prefix: "ic_"
+///     suffix: ""
+///     stripPathSegments: 1
+///     replace:
+///         "%": "percent"
+///     transliterate: true
+///     collapseUnderscores: true
+///     trim: true
+///     rename:
+///         "ic_24/Old Name": "ic_new_name"
+/// ```
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamingConfig {
+    /// Added to the beginning of the name, unless the name already starts with it
+    #[serde(default)]
+    pub prefix: String,
+    /// Added to the end of the name, unless the name already ends with it
+    #[serde(default)]
+    pub suffix: String,
+    /// How many leading segments of the path in the name, like `ic_24/` in `ic_24/search`,
+    /// are removed. The last segment is always kept
+    #[serde(default)]
+    pub strip_path_segments: usize,
+    /// Substrings of the Figma name which are replaced before converting it to snake_case
+    #[serde(default)]
+    pub replace: HashMap<String, String>,
+    /// Replace Cyrillic letters with Latin ones, e.g. `Поиск` with `Poisk`, and remove diacritics
+    /// from Latin letters. Otherwise non-Latin letters are replaced with underscores
+    #[serde(default)]
+    pub transliterate: bool,
+    /// Replace repeated underscores with a single one
+    #[serde(default)]
+    pub collapse_underscores: bool,
+    /// Remove underscores from the beginning and the end of the name
+    #[serde(default)]
+    pub trim: bool,
+    /// Resource names for specific Figma names, the other rules are not applied to them
+    #[serde(default)]
+    pub rename: HashMap<String, String>,
+}
+
+fn default_duplicates_rule() -> DuplicatesRule {
+    DuplicatesRule::Fail
+}
+
+fn default_collect_depth() -> u32 {
+    1
 }
 
 /// Part of App config from YAML:
@@ -121,13 +396,35 @@ pub struct CommonIconsConfig {
 ///             xhdpi: 2.0
 ///             xxhdpi: 3.0
 ///         format: svg | png | webp
+///         renderMode: figma | local | downscale
 ///         webpOptions:
 ///             quality: 0..100
+///             lossless: true | false
+///             alphaQuality: 0..100
+///             method: 0..6
+///             nearLossless: 0..100
+///         overrides:
+///             img_name:
+///                 renderMode: figma | local | downscale
+///                 webpOptions:
+///                     quality: 0..100
+///         routes:
+///             - regex: "^img_(onboarding|promo)_"
+///               mainRes: "./feature-onboarding/src/main/res"
 ///     icons:
 ///         mainRes: "./main/res"
 ///         format: svg | xml
+///         routes:
+///             - prefix: ic_payments_
+///               mainRes: "./feature-payments/src/main/res"
+///         verifyOptions:
+///             threshold: 0..100
+///             failOnMismatch: true | false
+///     colors:
+///         mainRes: "./main/res"
+///         fileName: figma_colors.xml
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AndroidConfig {
     pub main_res: Option<String>,
@@ -135,6 +432,8 @@ pub struct AndroidConfig {
     pub images: AndroidImagesConfig,
     #[serde(default = "default_android_icons_config")]
     pub icons: AndroidIconsConfig,
+    #[serde(default = "default_android_colors_config")]
+    pub colors: AndroidColorsConfig,
 }
 
 fn default_android_images_config() -> AndroidImagesConfig {
@@ -142,7 +441,10 @@ fn default_android_images_config() -> AndroidImagesConfig {
         main_res: None,
         scales: default_scales(),
         format: default_image_format(),
+        render_mode: default_render_mode(),
         webp_options: default_webp_options(),
+        overrides: HashMap::new(),
+        routes: vec![],
     }
 }
 
@@ -150,6 +452,15 @@ fn default_android_icons_config() -> AndroidIconsConfig {
     AndroidIconsConfig {
         main_res: None,
         format: IconFormat::Xml,
+        verify_options: default_verify_options(),
+        routes: vec![],
+    }
+}
+
+fn default_android_colors_config() -> AndroidColorsConfig {
+    AndroidColorsConfig {
+        main_res: None,
+        file_name: default_colors_file_name(),
     }
 }
 
@@ -163,10 +474,23 @@ fn default_android_icons_config() -> AndroidIconsConfig {
 ///         xhdpi: 2.0
 ///         xxhdpi: 3.0
 ///     format: svg | png | webp
+///     renderMode: figma | local | downscale
 ///     webpOptions:
 ///         quality: 0..100
+///         lossless: true | false
+///         alphaQuality: 0..100
+///         method: 0..6
+///         nearLossless: 0..100
+///     overrides:
+///         img_name:
+///             renderMode: figma | local | downscale
+///             webpOptions:
+///                 quality: 0..100
+///     routes:
+///         - regex: "^img_(onboarding|promo)_"
+///           mainRes: "./feature-onboarding/src/main/res"
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AndroidImagesConfig {
     pub main_res: Option<String>,
@@ -174,8 +498,16 @@ pub struct AndroidImagesConfig {
     pub scales: HashMap<String, f32>,
     #[serde(default = "default_image_format")]
     pub format: ImageFormat,
+    #[serde(default = "default_render_mode")]
+    pub render_mode: ImageRenderMode,
     #[serde(default = "default_webp_options")]
     pub webp_options: AndroidImagesWebpConfig,
+    /// Settings for specific images by their resource names, e.g. `img_photo`
+    #[serde(default)]
+    pub overrides: HashMap<String, AndroidImageOverrideConfig>,
+    /// Rules which choose `mainRes` by the resource name. `mainRes` is the fallback
+    #[serde(default)]
+    pub routes: Vec<AndroidRouteConfig>,
 }
 
 fn default_scales() -> HashMap<String, f32> {
@@ -194,8 +526,34 @@ fn default_image_format() -> ImageFormat {
     ImageFormat::Webp
 }
 
+fn default_render_mode() -> ImageRenderMode {
+    ImageRenderMode::Figma
+}
+
 fn default_webp_options() -> AndroidImagesWebpConfig {
-    AndroidImagesWebpConfig { quality: 85f32 }
+    AndroidImagesWebpConfig {
+        quality: default_webp_quality(),
+        lossless: false,
+        alpha_quality: default_webp_alpha_quality(),
+        method: default_webp_method(),
+        near_lossless: default_webp_near_lossless(),
+    }
+}
+
+fn default_webp_quality() -> f32 {
+    85f32
+}
+
+fn default_webp_alpha_quality() -> u8 {
+    100
+}
+
+fn default_webp_method() -> u8 {
+    4
+}
+
+fn default_webp_near_lossless() -> u8 {
+    100
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -223,14 +581,71 @@ impl ImageFormat {
     }
 }
 
+/// How PNG and WEBP images are rasterized for every scale.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageRenderMode {
+    /// Figma renders a separate PNG for every scale
+    Figma,
+    /// SVG is downloaded once and every scale is rendered locally.
+    /// Images with effects which can't be rendered locally are rendered by Figma.
+    Local,
+    /// Figma renders only the largest scale, smaller scales are resampled from it locally
+    Downscale,
+}
+
 /// Part of App config from YAML:
 /// ```yaml
 /// webpOptions:
 ///     quality: 0..100
+///     lossless: true | false
+///     alphaQuality: 0..100
+///     method: 0..6
+///     nearLossless: 0..100
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct AndroidImagesWebpConfig {
+    /// Quality of lossy encoding, or compression effort of lossless encoding
+    #[serde(default = "default_webp_quality")]
     pub quality: f32,
+    #[serde(default)]
+    pub lossless: bool,
+    #[serde(default = "default_webp_alpha_quality")]
+    pub alpha_quality: u8,
+    /// Trade-off between encoding speed and size: 0 is the fastest, 6 is the smallest
+    #[serde(default = "default_webp_method")]
+    pub method: u8,
+    /// Level of lossless preprocessing, 100 turns it off. Used with lossless encoding only
+    #[serde(default = "default_webp_near_lossless")]
+    pub near_lossless: u8,
+}
+
+/// The same as [AndroidImagesWebpConfig], but omitted fields are taken from the global
+/// `webpOptions` instead of defaults.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidImageWebpOverrideConfig {
+    pub quality: Option<f32>,
+    pub lossless: Option<bool>,
+    pub alpha_quality: Option<u8>,
+    pub method: Option<u8>,
+    pub near_lossless: Option<u8>,
+}
+
+/// Part of App config from YAML with settings for the specific image:
+/// ```yaml
+/// overrides:
+///     img_name:
+///         renderMode: figma | local | downscale
+///         webpOptions:
+///             quality: 0..100
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidImageOverrideConfig {
+    pub render_mode: Option<ImageRenderMode>,
+    pub webp_options: Option<AndroidImageWebpOverrideConfig>,
 }
 
 /// Part of App config from YAML:
@@ -238,19 +653,111 @@ pub struct AndroidImagesWebpConfig {
 /// icons:
 ///     mainRes: "./main/res"
 ///     format: svg | xml
+///     verifyOptions:
+///         threshold: 0..100
+///         failOnMismatch: true | false
+///     routes:
+///         - prefix: ic_payments_
+///           mainRes: "./feature-payments/src/main/res"
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AndroidIconsConfig {
     pub main_res: Option<String>,
     #[serde(default = "default_icons_format")]
     pub format: IconFormat,
+    #[serde(default = "default_verify_options")]
+    pub verify_options: AndroidIconsVerifyConfig,
+    /// Rules which choose `mainRes` by the resource name. `mainRes` is the fallback
+    #[serde(default)]
+    pub routes: Vec<AndroidRouteConfig>,
+}
+
+/// Rule which sends resources with matching names to another `mainRes`, e.g. to a feature
+/// module. Part of App config from YAML:
+/// ```yaml
+/// routes:
+///     - prefix: ic_payments_
+///       mainRes: "./feature-payments/src/main/res"
+///     - regex: "^img_(onboarding|promo)_"
+///       mainRes: "./feature-onboarding/src/main/res"
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidRouteConfig {
+    pub prefix: Option<String>,
+    /// Compiled when the config is read, so invalid patterns fail the parsing
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub regex: Option<Regex>,
+    pub main_res: String,
+}
+
+impl AndroidRouteConfig {
+    /// Returns `true` if the resource name matches the prefix or the regex of the rule.
+    pub fn matches(&self, res_name: &str) -> bool {
+        let prefix_matches = self
+            .prefix
+            .as_ref()
+            .is_some_and(|prefix| res_name.starts_with(prefix.as_str()));
+        let regex_matches = self
+            .regex
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(res_name));
+        prefix_matches || regex_matches
+    }
+
+    /// Description of the rule for messages, e.g. `prefix ic_payments_`
+    pub fn describe(&self) -> String {
+        match (&self.prefix, &self.regex) {
+            (Some(prefix), _) => format!("prefix {}", prefix),
+            (None, Some(regex)) => format!("regex {}", regex),
+            (None, None) => "empty rule".to_string(),
+        }
+    }
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(pattern) => Regex::new(&pattern)
+            .map(Some)
+            .map_err(|e| D::Error::custom(format!("invalid regex `{}`: {}", pattern, e))),
+        None => Ok(None),
+    }
 }
 
 fn default_icons_format() -> IconFormat {
     IconFormat::Xml
 }
 
+fn default_verify_options() -> AndroidIconsVerifyConfig {
+    AndroidIconsVerifyConfig {
+        threshold: default_verify_threshold(),
+        fail_on_mismatch: false,
+    }
+}
+
+fn default_verify_threshold() -> f32 {
+    1f32
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// verifyOptions:
+///     threshold: 0..100
+///     failOnMismatch: true | false
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidIconsVerifyConfig {
+    #[serde(default = "default_verify_threshold")]
+    pub threshold: f32,
+    #[serde(default)]
+    pub fail_on_mismatch: bool,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum IconFormat {
@@ -258,6 +765,26 @@ pub enum IconFormat {
     Xml,
 }
 
+/// Part of App config from YAML:
+/// ```yaml
+/// colors:
+///     mainRes: "./main/res"
+///     fileName: figma_colors.xml
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidColorsConfig {
+    pub main_res: Option<String>,
+    /// File in `values` and `values-night` dirs, which is rewritten entirely on every export,
+    /// so it shouldn't contain colors added by hand
+    #[serde(default = "default_colors_file_name")]
+    pub file_name: String,
+}
+
+fn default_colors_file_name() -> String {
+    "figma_colors.xml".to_string()
+}
+
 impl IconFormat {
     pub fn extension(&self) -> String {
         match &self {
@@ -279,6 +806,132 @@ impl AppConfig {
         }
     }
 
+    /// Returns Figma frames with resources of the type. Without `figma.sources` of the type,
+    /// resources are looked for in `figma.fileId` in the frame from the `common` section.
+    pub fn sources(&self, resource_type: &ResourceType) -> Vec<FigmaSourceConfig> {
+        let sources: Vec<FigmaSourceConfig> = self
+            .figma
+            .sources
+            .iter()
+            .filter(|source| &source.resource_type == resource_type)
+            .cloned()
+            .collect();
+        if !sources.is_empty() || self.figma.file_id.is_empty() {
+            return sources;
+        }
+        let figma_frame_name = match resource_type {
+            ResourceType::Images => &self.common.images.figma_frame_name,
+            ResourceType::Icons => &self.common.icons.figma_frame_name,
+            ResourceType::Colors => &self.common.colors.figma_frame_name,
+        };
+        vec![FigmaSourceConfig {
+            file_id: self.figma.file_id.clone(),
+            page_name: self.figma.page_name.clone(),
+            figma_frame_name: figma_frame_name.clone(),
+            resource_type: resource_type.clone(),
+        }]
+    }
+
+    /// Returns the config for exporting the target: its frame is the only source of resources
+    /// of its type, and its output location and format replace the ones from `android`.
+    pub fn for_target(mut self, target: &TargetConfig) -> Result<Self, AppError> {
+        let file_id = self.target_file_id(target);
+        if file_id.is_empty() {
+            return Err(AppError::TargetMissingFileId(target.name.clone()));
+        }
+        self.figma.sources = vec![FigmaSourceConfig {
+            file_id,
+            page_name: target
+                .page_name
+                .clone()
+                .or_else(|| self.figma.page_name.clone()),
+            figma_frame_name: target.figma_frame_name.clone(),
+            resource_type: target.resource_type.clone(),
+        }];
+
+        let invalid_format = |format: &TargetFormat| {
+            let format = format!("{:?}", format).to_lowercase();
+            AppError::TargetInvalidFormat(target.name.clone(), format)
+        };
+        match target.resource_type {
+            ResourceType::Images => {
+                let images = &mut self.android.images;
+                if target.main_res.is_some() {
+                    images.main_res = target.main_res.clone();
+                    images.routes.clear();
+                }
+                images.format = match &target.format {
+                    None => images.format.clone(),
+                    Some(TargetFormat::Svg) => ImageFormat::Svg,
+                    Some(TargetFormat::Png) => ImageFormat::Png,
+                    Some(TargetFormat::Webp) => ImageFormat::Webp,
+                    Some(format @ TargetFormat::Xml) => return Err(invalid_format(format)),
+                };
+            }
+            ResourceType::Icons => {
+                let icons = &mut self.android.icons;
+                if target.main_res.is_some() {
+                    icons.main_res = target.main_res.clone();
+                    icons.routes.clear();
+                }
+                icons.format = match &target.format {
+                    None => icons.format.clone(),
+                    Some(TargetFormat::Svg) => IconFormat::Svg,
+                    Some(TargetFormat::Xml) => IconFormat::Xml,
+                    Some(format @ (TargetFormat::Png | TargetFormat::Webp)) => {
+                        return Err(invalid_format(format))
+                    }
+                };
+            }
+            ResourceType::Colors => {
+                if let Some(format) = &target.format {
+                    return Err(invalid_format(format));
+                }
+                if target.main_res.is_some() {
+                    self.android.colors.main_res = target.main_res.clone();
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Returns names of the frames with resources of the type, for messages.
+    pub fn frame_names(&self, resource_type: &ResourceType) -> String {
+        self.sources(resource_type)
+            .iter()
+            .map(|source| source.figma_frame_name.clone())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Returns id of the Figma file with the frame of the target, empty if there is none.
+    pub fn target_file_id(&self, target: &TargetConfig) -> String {
+        target
+            .file_id
+            .clone()
+            .unwrap_or_else(|| self.figma.file_id.clone())
+    }
+
+    /// Returns ids of all Figma files which resources are exported from, by the sources
+    /// and by the targets.
+    pub fn file_ids(&self) -> BTreeSet<String> {
+        let target_file_ids = self
+            .targets
+            .iter()
+            .map(|target| self.target_file_id(target));
+        [
+            ResourceType::Images,
+            ResourceType::Icons,
+            ResourceType::Colors,
+        ]
+        .iter()
+        .flat_map(|resource_type| self.sources(resource_type))
+        .map(|source| source.file_id)
+        .chain(target_file_ids)
+        .filter(|file_id| !file_id.is_empty())
+        .collect()
+    }
+
     /// Returns the required mainRes path from config.
     pub fn main_res_images(&self) -> Option<String> {
         let common_main_res = self.android.main_res.clone();
@@ -287,6 +940,53 @@ impl AppConfig {
         images_main_res.or(common_main_res)
     }
 
+    /// Returns mainRes path for the image, chosen by the first matching routing rule.
+    /// Returns the rule too, if any of them matches.
+    pub fn image_main_res(&self, res_name: &str) -> (Option<String>, Option<&AndroidRouteConfig>) {
+        route(
+            &self.android.images.routes,
+            res_name,
+            self.main_res_images(),
+        )
+    }
+
+    /// Returns mainRes path for the icon, chosen by the first matching routing rule.
+    /// Returns the rule too, if any of them matches.
+    pub fn icon_main_res(&self, res_name: &str) -> (Option<String>, Option<&AndroidRouteConfig>) {
+        route(&self.android.icons.routes, res_name, self.main_res_icons())
+    }
+
+    /// Returns render mode for the image with the resource name, taking into account its overrides.
+    pub fn image_render_mode(&self, res_name: &str) -> &ImageRenderMode {
+        let images = &self.android.images;
+        images
+            .overrides
+            .get(res_name)
+            .and_then(|o| o.render_mode.as_ref())
+            .unwrap_or(&images.render_mode)
+    }
+
+    /// Returns WEBP encoding options for the image with the resource name, taking into account
+    /// its overrides.
+    pub fn image_webp_options(&self, res_name: &str) -> AndroidImagesWebpConfig {
+        let images = &self.android.images;
+        let global = images.webp_options.clone();
+        match images
+            .overrides
+            .get(res_name)
+            .and_then(|o| o.webp_options.as_ref())
+        {
+            Some(o) => AndroidImagesWebpConfig {
+                quality: o.quality.unwrap_or(global.quality),
+                lossless: o.lossless.unwrap_or(global.lossless),
+                alpha_quality: o.alpha_quality.unwrap_or(global.alpha_quality),
+                method: o.method.unwrap_or(global.method),
+                near_lossless: o.near_lossless.unwrap_or(global.near_lossless),
+            },
+            None => global,
+        }
+    }
+
     /// Returns the required mainRes path from config.
     pub fn main_res_icons(&self) -> Option<String> {
         let common_main_res = self.android.main_res.clone();
@@ -294,4 +994,275 @@ impl AppConfig {
 
         icons_main_res.or(common_main_res)
     }
+
+    /// Returns the required mainRes path from config.
+    pub fn main_res_colors(&self) -> Option<String> {
+        let common_main_res = self.android.main_res.clone();
+        let colors_main_res = self.android.colors.main_res.clone();
+
+        colors_main_res.or(common_main_res)
+    }
+}
+
+fn route<'a>(
+    routes: &'a [AndroidRouteConfig],
+    res_name: &str,
+    fallback: Option<String>,
+) -> (Option<String>, Option<&'a AndroidRouteConfig>) {
+    match routes.iter().find(|route| route.matches(res_name)) {
+        Some(route) => (Some(route.main_res.clone()), Some(route)),
+        None => (fallback, None),
+    }
+}
+
+/// Where the resources go by the routing rules, to tell the user about it when they are spread
+/// over several modules. Returns res names, sorted and unique, with their mainRes paths and
+/// descriptions of the matching rules, `None` for the fallback. Returns nothing without rules.
+pub fn describe_routes<'a>(
+    routes: &[AndroidRouteConfig],
+    res_names: impl IntoIterator<Item = &'a String>,
+    fallback: Option<String>,
+) -> Vec<(String, String, Option<String>)> {
+    if routes.is_empty() {
+        return Vec::new();
+    }
+    let res_names: BTreeSet<&String> = res_names.into_iter().collect();
+    res_names
+        .into_iter()
+        .map(|res_name| {
+            let (main_res, route) = route(routes, res_name, fallback.clone());
+            (
+                res_name.clone(),
+                main_res.unwrap_or_default(),
+                route.map(|route| route.describe()),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_sources() {
+    let app_config: AppConfig = serde_yaml::from_str(
+        r#"
+        figma:
+            fileId: PRODUCT
+            pageName: Android
+            sources:
+                - fileId: LIBRARY
+                  figmaFrameName: Icons
+                  resourceType: icons
+                - fileId: FEATURE
+                  pageName: Payments
+                  figmaFrameName: Icons
+                  resourceType: icons
+                - fileId: BRAND
+                  figmaFrameName: Palette
+                  resourceType: colors
+        android:
+            mainRes: ./res
+        targets:
+            - name: library-illustrations
+              resourceType: images
+              fileId: LIBRARY_ILLUSTRATIONS
+              figmaFrameName: Illustrations
+            - name: product-colors
+              resourceType: colors
+              figmaFrameName: Colors
+        "#,
+    )
+    .unwrap();
+    let describe = |resource_type: ResourceType| {
+        app_config
+            .sources(&resource_type)
+            .into_iter()
+            .map(|s| format!("{} {:?} {}", s.file_id, s.page_name, s.figma_frame_name))
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(
+        describe(ResourceType::Images),
+        vec!["PRODUCT Some(\"Android\") Images"]
+    );
+    assert_eq!(
+        describe(ResourceType::Icons),
+        vec!["LIBRARY None Icons", "FEATURE Some(\"Payments\") Icons"]
+    );
+    assert_eq!(
+        app_config.file_ids().into_iter().collect::<Vec<String>>(),
+        vec![
+            "BRAND",
+            "FEATURE",
+            "LIBRARY",
+            "LIBRARY_ILLUSTRATIONS",
+            "PRODUCT"
+        ]
+    );
+}
+
+#[test]
+fn test_routes() {
+    let app_config: AppConfig = serde_yaml::from_str(
+        r#"
+        figma:
+            fileId: FILE
+        android:
+            mainRes: ./core-ui/res
+            icons:
+                routes:
+                    - prefix: ic_payments_
+                      mainRes: ./feature-payments/res
+                    - regex: "^ic_(profile|settings)_"
+                      mainRes: ./feature-profile/res
+        "#,
+    )
+    .unwrap();
+    let main_res = |res_name: &str| app_config.icon_main_res(res_name).0.unwrap();
+
+    assert_eq!(main_res("ic_payments_card"), "./feature-payments/res");
+    assert_eq!(main_res("ic_settings_gear"), "./feature-profile/res");
+    assert_eq!(main_res("ic_search"), "./core-ui/res");
+    assert_eq!(
+        app_config.image_main_res("ic_payments_card").0.unwrap(),
+        "./core-ui/res"
+    );
+
+    let names = ["ic_search", "ic_payments_card", "ic_search"].map(|n| n.to_string());
+    assert_eq!(
+        describe_routes(
+            &app_config.android.icons.routes,
+            &names,
+            app_config.main_res_icons()
+        ),
+        vec![
+            (
+                "ic_payments_card".to_string(),
+                "./feature-payments/res".to_string(),
+                Some("prefix ic_payments_".to_string())
+            ),
+            ("ic_search".to_string(), "./core-ui/res".to_string(), None),
+        ]
+    );
+    assert!(describe_routes(&[], &names, app_config.main_res_images()).is_empty());
+
+    let invalid = serde_yaml::from_str::<AppConfig>(
+        r#"
+        android:
+            icons:
+                routes:
+                    - regex: "^ic_(profile"
+                      mainRes: ./feature-profile/res
+        "#,
+    );
+    assert!(invalid.unwrap_err().to_string().contains("invalid regex"));
+}
+
+#[test]
+fn test_for_target() {
+    let yaml = r#"
+        figma:
+            fileId: PRODUCT
+        android:
+            mainRes: ./core-ui/res
+            icons:
+                routes:
+                    - prefix: ic_payments_
+                      mainRes: ./feature-payments/res
+        targets:
+            - name: payments-icons
+              resourceType: icons
+              figmaFrameName: Payments
+              mainRes: ./feature-payments/res
+            - name: library-illustrations
+              resourceType: images
+              fileId: LIBRARY
+              figmaFrameName: Illustrations
+              format: webp
+            - name: broken
+              resourceType: icons
+              figmaFrameName: Icons
+              format: png
+            - name: brand-colors
+              resourceType: colors
+              figmaFrameName: Brand
+              mainRes: ./brand/res
+            - name: broken-colors
+              resourceType: colors
+              figmaFrameName: Colors
+              format: xml
+        "#;
+    let targets = serde_yaml::from_str::<AppConfig>(yaml).unwrap().targets;
+    let for_target = |index: usize| {
+        let app_config: AppConfig = serde_yaml::from_str(yaml).unwrap();
+        app_config.for_target(&targets[index])
+    };
+
+    let icons = for_target(0).unwrap();
+    let sources = icons.sources(&ResourceType::Icons);
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].file_id, "PRODUCT");
+    assert_eq!(sources[0].figma_frame_name, "Payments");
+    assert!(icons.android.icons.routes.is_empty());
+    assert_eq!(
+        icons.icon_main_res("ic_card").0.unwrap(),
+        "./feature-payments/res"
+    );
+
+    let images = for_target(1).unwrap();
+    assert_eq!(images.sources(&ResourceType::Images)[0].file_id, "LIBRARY");
+    assert!(matches!(images.android.images.format, ImageFormat::Webp));
+    assert_eq!(
+        images.image_main_res("img_card").0.unwrap(),
+        "./core-ui/res"
+    );
+
+    assert_eq!(
+        for_target(2).unwrap_err().to_string(),
+        "Target `broken` can't be exported in format png"
+    );
+
+    let colors = for_target(3).unwrap();
+    assert_eq!(
+        colors.sources(&ResourceType::Colors)[0].figma_frame_name,
+        "Brand"
+    );
+    assert_eq!(colors.main_res_colors().unwrap(), "./brand/res");
+    assert_eq!(colors.android.colors.file_name, "figma_colors.xml");
+    assert_eq!(
+        for_target(4).unwrap_err().to_string(),
+        "Target `broken-colors` can't be exported in format xml"
+    );
+}
+
+#[test]
+fn test_image_overrides() {
+    let app_config: AppConfig = serde_yaml::from_str(
+        r#"
+        figma:
+            fileId: FILE
+        android:
+            mainRes: ./res
+            images:
+                renderMode: local
+                webpOptions:
+                    quality: 90
+                overrides:
+                    img_photo:
+                        renderMode: downscale
+                        webpOptions:
+                            lossless: true
+        "#,
+    )
+    .unwrap();
+    // Overrides are looked up by resource names, not by Figma names like `Img/Photo`
+    assert!(matches!(
+        app_config.image_render_mode("img_photo"),
+        ImageRenderMode::Downscale
+    ));
+    assert!(matches!(
+        app_config.image_render_mode("img_logo"),
+        ImageRenderMode::Local
+    ));
+    let webp_options = app_config.image_webp_options("img_photo");
+    assert!(webp_options.lossless);
+    assert_eq!(webp_options.quality, 90f32);
 }
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::common::error::AppError;
+
+const CREDENTIALS_DIR_NAME: &str = "fxa";
+const CREDENTIALS_FILE_NAME: &str = "credentials.yaml";
+
+/// User level credentials, which are used when the token is not passed with `-t` or
+/// `FIGMA_PERSONAL_TOKEN` env variable:
+/// ```yaml
+/// personalToken: "..."
+/// ```
+/// or, after `fxa auth oauth`:
+/// ```yaml
+/// oauth:
+///     clientId: "..."
+///     clientSecret: "..."
+///     accessToken: "..."
+///     refreshToken: "..."
+///     expiresAt: 1656000000
+/// ```
+/// The file is saved to `$XDG_CONFIG_HOME/fxa/credentials.yaml` (`~/.config/fxa/credentials.yaml`
+/// by default) or `%APPDATA%\fxa\credentials.yaml` on Windows, and is readable by its owner only.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Credentials {
+    #[serde(default)]
+    pub personal_token: Option<String>,
+    #[serde(default)]
+    pub oauth: Option<OAuthCredentials>,
+}
+
+/// Tokens of Figma OAuth app with the app credentials to refresh the access token.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthCredentials {
+    pub client_id: String,
+    pub client_secret: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Unix time in seconds when the access token expires
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+/// Token to authorize requests to Figma API.
+pub enum FigmaToken {
+    /// Personal access token, sent in `X-FIGMA-TOKEN` header
+    Personal(String),
+    /// OAuth access token, sent in `Authorization: Bearer` header
+    OAuth(OAuthCredentials),
+}
+
+/// Response of Figma API with new OAuth tokens.
+///
+/// Details: https://www.figma.com/developers/api#oauth2
+#[derive(Debug, Deserialize)]
+pub struct OAuthTokenResponse {
+    pub access_token: String,
+    /// Figma doesn't return new refresh token when the access token is refreshed
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    pub expires_in: u64,
+}
+
+/// Where the token is taken from.
+pub enum TokenSource {
+    /// `-t` argument or `FIGMA_PERSONAL_TOKEN` env variable
+    Argument,
+    /// User level credentials file
+    CredentialsFile(String),
+}
+
+impl Credentials {
+    /// Path to the user level credentials file, if the home dir of the user is known.
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = if cfg!(windows) {
+            std::env::var_os("APPDATA").map(PathBuf::from)
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+                })
+        };
+        config_dir.map(|dir| dir.join(CREDENTIALS_DIR_NAME).join(CREDENTIALS_FILE_NAME))
+    }
+
+    /// Read the credentials file. Returns empty credentials if there is no file yet.
+    pub fn from_file() -> Result<Self, AppError> {
+        let path = match Credentials::path() {
+            Some(path) => path,
+            None => return Ok(Credentials::default()),
+        };
+        let path_name = path.to_string_lossy().to_string();
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_yaml::from_str(&content)
+                .map_err(|e| AppError::CredentialsRead(path_name, e.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Credentials::default()),
+            Err(e) => Err(AppError::CredentialsRead(path_name, e.to_string())),
+        }
+    }
+
+    /// Write the credentials file, so only its owner can read it. Returns the path to the file.
+    pub fn save(&self) -> Result<String, AppError> {
+        let path = Credentials::path().ok_or_else(|| {
+            AppError::CredentialsWrite(
+                CREDENTIALS_FILE_NAME.to_string(),
+                "can't find home directory".to_string(),
+            )
+        })?;
+        let path_name = path.to_string_lossy().to_string();
+        let content = serde_yaml::to_string(self)
+            .map_err(|e| AppError::CredentialsWrite(path_name.clone(), e.to_string()))?;
+        write_private_file(&path, content.as_bytes())
+            .map_err(|e| AppError::CredentialsWrite(path_name.clone(), e.to_string()))?;
+        Ok(path_name)
+    }
+}
+
+impl OAuthCredentials {
+    pub fn new(client_id: &str, client_secret: &str, response: OAuthTokenResponse) -> Self {
+        let mut credentials = Self {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            access_token: String::new(),
+            refresh_token: String::new(),
+            expires_at: None,
+        };
+        credentials.update(response);
+        credentials
+    }
+
+    /// Take new tokens from the response of Figma API.
+    pub fn update(&mut self, response: OAuthTokenResponse) {
+        self.access_token = response.access_token;
+        if let Some(refresh_token) = response.refresh_token {
+            self.refresh_token = refresh_token;
+        }
+        self.expires_at = Some(unix_time() + response.expires_in);
+    }
+
+    /// Returns `true` if the access token expires within a minute.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= unix_time() + 60)
+    }
+}
+
+/// Take the token from the argument, or from the credentials file if there is no argument.
+/// OAuth tokens have priority over the personal token in the credentials file.
+pub fn resolve_token(token: &Option<String>) -> Result<(FigmaToken, TokenSource), AppError> {
+    if let Some(token) = token {
+        return Ok((FigmaToken::Personal(token.clone()), TokenSource::Argument));
+    }
+    let credentials = Credentials::from_file()?;
+    let source = TokenSource::CredentialsFile(
+        Credentials::path()
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    );
+    match (credentials.oauth, credentials.personal_token) {
+        (Some(oauth), _) => Ok((FigmaToken::OAuth(oauth), source)),
+        (None, Some(token)) => Ok((FigmaToken::Personal(token), source)),
+        (None, None) => Err(AppError::MissingToken),
+    }
+}
+
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Write the file atomically with permissions for the owner only.
+fn write_private_file(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let dir = path
+        .parent()
+        .expect("Credentials file is inside the config dir");
+    fs::create_dir_all(dir)?;
+    let temporary_path = path.with_extension("fxa-tmp");
+    // The mode is set only when the file is created, so a file left by a failed write is removed
+    fs::remove_file(&temporary_path).unwrap_or_default();
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let result = options
+        .open(&temporary_path)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temporary_path, path));
+    if result.is_err() {
+        fs::remove_file(&temporary_path).unwrap_or_default();
+    }
+    result
+}
+
+#[cfg(unix)]
+#[test]
+fn test_credentials_file_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(".fxa").join(CREDENTIALS_FILE_NAME);
+    write_private_file(&path, b"personalToken: token").unwrap();
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let temporary_path = path.with_extension("fxa-tmp");
+    fs::write(&temporary_path, b"").unwrap();
+    fs::set_permissions(&temporary_path, fs::Permissions::from_mode(0o644)).unwrap();
+    write_private_file(&path, b"personalToken: other").unwrap();
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(fs::read(&path).unwrap(), b"personalToken: other");
+}
@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgEnum, Parser, Subcommand};
 
 /// Simple util to export resources from figma to android project
 #[derive(Parser, Debug)]
@@ -13,32 +13,201 @@ pub struct Args {
 pub enum Command {
     /// Export images/illustrations from Figma
     Images {
-        /// Figma personal access token, can be omitted if there is a env variable
+        /// Figma personal access token, can be omitted if there is a env variable or `fxa auth login` is done
         #[clap(short, long, env = "FIGMA_PERSONAL_TOKEN")]
-        token: String,
+        token: Option<String>,
         /// Path to yaml config. Use `fxn config` to generate default config here
         #[clap(short = 'c', long = "config")]
         path_to_config: String,
+        /// Save intermediate files to `.fxa` dir for debugging
+        #[clap(long)]
+        keep_temp_files: bool,
+        /// Write successfully exported resources even if some of them failed
+        #[clap(long)]
+        allow_partial: bool,
+        /// Link to the Figma node to export instead of the names, e.g. from `Copy link to selection`.
+        /// The name of the node is used, unless a name is given
+        #[clap(long)]
+        url: Option<String>,
         /// Space separated images names
         names: Vec<String>,
     },
     /// Export vector icons from Figma
     Icons {
-        /// Figma personal access token, can be omitted if there is a env variable
+        /// Figma personal access token, can be omitted if there is a env variable or `fxa auth login` is done
         #[clap(short, long, env = "FIGMA_PERSONAL_TOKEN")]
-        token: String,
+        token: Option<String>,
         /// Path to yaml config. Use `fxn config` to generate default config here
         #[clap(short = 'c', long = "config")]
         path_to_config: String,
+        /// Compare rendered VectorDrawable with the original SVG from Figma
+        #[clap(long)]
+        verify: bool,
+        /// Save intermediate files to `.fxa` dir for debugging
+        #[clap(long)]
+        keep_temp_files: bool,
+        /// Write successfully exported resources even if some of them failed
+        #[clap(long)]
+        allow_partial: bool,
+        /// Link to the Figma node to export instead of the names, e.g. from `Copy link to selection`.
+        /// The name of the node is used, unless a name is given
+        #[clap(long)]
+        url: Option<String>,
         /// Space separated images names
         names: Vec<String>,
     },
+    /// Export `targets` of the config in one pass, fetching every Figma file once
+    Export {
+        /// Figma personal access token, can be omitted if there is a env variable or `fxa auth login` is done
+        #[clap(short, long, env = "FIGMA_PERSONAL_TOKEN")]
+        token: Option<String>,
+        /// Path to yaml config. Use `fxn config` to generate default config here
+        #[clap(short = 'c', long = "config")]
+        path_to_config: String,
+        /// Compare rendered VectorDrawable of icon targets with the original SVG from Figma
+        #[clap(long)]
+        verify: bool,
+        /// Save intermediate files to `.fxa` dir for debugging
+        #[clap(long)]
+        keep_temp_files: bool,
+        /// Write successfully exported resources even if some of them failed
+        #[clap(long)]
+        allow_partial: bool,
+        /// Space separated target names. All targets are exported if omitted
+        targets: Vec<String>,
+    },
     /// Generate default yaml config here
     Config {
         /// New config filename
         #[clap(default_value_t = String::from("fxn_default_config.yaml"))]
         new_config_filename: String,
     },
+    /// Delete exported resources which no longer exist in Figma
+    Prune {
+        /// Figma personal access token, can be omitted if there is a env variable or `fxa auth login` is done
+        #[clap(short, long, env = "FIGMA_PERSONAL_TOKEN")]
+        token: Option<String>,
+        /// Path to yaml config. Use `fxn config` to generate default config here
+        #[clap(short = 'c', long = "config")]
+        path_to_config: String,
+        /// Delete stale files without confirmation
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// Find exported resources which are never referenced in the project sources
+    Unused {
+        /// Path to yaml config. Use `fxn config` to generate default config here
+        #[clap(short = 'c', long = "config")]
+        path_to_config: String,
+        /// Root dir of the Android project with Kotlin, Java and XML sources
+        #[clap(short = 'p', long = "project", default_value_t = String::from("."))]
+        project_dir: String,
+        /// Delete the files of unused resources
+        #[clap(long)]
+        prune: bool,
+        /// Delete unused files without confirmation
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// Re-export resources of the configured frames every time the Figma file changes
+    Watch {
+        /// Figma personal access token, can be omitted if there is a env variable or `fxa auth login` is done
+        #[clap(short, long, env = "FIGMA_PERSONAL_TOKEN")]
+        token: Option<String>,
+        /// Path to yaml config. Use `fxn config` to generate default config here
+        #[clap(short = 'c', long = "config")]
+        path_to_config: String,
+        /// Interval between checks of the Figma file version, in seconds
+        #[clap(short, long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+    },
+    /// Receive Figma webhooks and re-export resources of the configured frames of the updated files
+    ServeWebhook {
+        /// Figma personal access token, can be omitted if there is a env variable or `fxa auth login` is done
+        #[clap(short, long, env = "FIGMA_PERSONAL_TOKEN")]
+        token: Option<String>,
+        /// Paths to yaml configs. The configs with `figma.fileId` of the updated file are synced
+        #[clap(short = 'c', long = "config", required = true)]
+        paths_to_configs: Vec<String>,
+        /// Port to listen on
+        #[clap(short, long, default_value_t = 8080)]
+        port: u16,
+        /// Passcode specified during the webhook creation
+        #[clap(long, env = "FIGMA_WEBHOOK_PASSCODE")]
+        passcode: String,
+        /// File to write the summary of the last sync to
+        #[clap(long = "summary")]
+        summary_path: Option<String>,
+    },
+    /// Check Figma token or save it for the other commands
+    Auth {
+        #[clap(subcommand)]
+        subcommand: AuthCommand,
+    },
     /// Clear temporary `.fxa` dir
     Cleanup,
+    /// Convert icons between SVG and Android Vector Drawable XML
+    Convert {
+        /// Target format of the converted files
+        #[clap(long = "to", arg_enum)]
+        to: ConvertFormat,
+        /// Output directory. By default, files are saved next to the original ones
+        #[clap(short = 'o', long = "output")]
+        output_dir: Option<String>,
+        /// Space separated paths to files for conversion
+        #[clap(required = true)]
+        files: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthCommand {
+    /// Show whose token is used and check that it is valid
+    Status {
+        /// Figma personal access token, can be omitted if there is a env variable or `fxa auth login` is done
+        #[clap(short, long, env = "FIGMA_PERSONAL_TOKEN")]
+        token: Option<String>,
+    },
+    /// Save Figma personal access token to the user level credentials file
+    Login {
+        /// Figma personal access token. It is asked interactively if omitted
+        #[clap(short, long)]
+        token: Option<String>,
+    },
+    /// Authorize Figma OAuth app in the browser and save its tokens to the user level credentials file
+    Oauth {
+        /// Client id of Figma OAuth app
+        #[clap(long, env = "FIGMA_OAUTH_CLIENT_ID")]
+        client_id: String,
+        /// Client secret of Figma OAuth app
+        #[clap(long, env = "FIGMA_OAUTH_CLIENT_SECRET")]
+        client_secret: String,
+        /// Port of the local server for the redirect. Add `http://localhost:<port>/callback` to the callback urls of the app
+        #[clap(short, long, default_value_t = 8765)]
+        port: u16,
+        /// Comma separated scopes of the access
+        #[clap(long, default_value = "file_content:read,current_user:read")]
+        scope: String,
+    },
+}
+
+#[derive(ArgEnum, Clone, Debug)]
+pub enum ConvertFormat {
+    /// SVG to Android Vector Drawable XML
+    Xml,
+    /// Android Vector Drawable XML to SVG
+    Svg,
+}
+
+#[test]
+fn test_watch_interval() {
+    let parse = |interval: &str| {
+        Args::try_parse_from(["fxa", "watch", "-c", "fxa.yaml", "--interval", interval])
+    };
+    // Zero interval would poll Figma without any delay
+    assert!(parse("0").is_err());
+    assert!(matches!(
+        parse("1").unwrap().subcommand,
+        Command::Watch { interval: 1, .. }
+    ));
 }
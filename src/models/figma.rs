@@ -18,5 +18,155 @@ pub struct Canvas {
 pub struct Frame {
     pub id: String,
     pub name: String,
+    /// Type of the node, e.g. `FRAME`, `SECTION`, `GROUP`, `COMPONENT`.
+    /// Documents cached by older versions of the app have no types
+    #[serde(rename = "type", default)]
+    pub node_type: String,
     pub children: Option<Vec<Frame>>,
+    #[serde(rename = "absoluteBoundingBox")]
+    pub absolute_bounding_box: Option<Rectangle>,
+    /// Bounds including effects like shadows, `null` for invisible nodes
+    #[serde(rename = "absoluteRenderBounds")]
+    pub absolute_render_bounds: Option<Rectangle>,
+    /// Documents cached by older versions of the app have no fills
+    #[serde(default)]
+    pub fills: Vec<Paint>,
+}
+
+impl Frame {
+    /// Returns bounds of the node as it is rendered by Figma, with its effects.
+    pub fn render_bounds(&self) -> Option<&Rectangle> {
+        self.absolute_render_bounds
+            .as_ref()
+            .or(self.absolute_bounding_box.as_ref())
+    }
+
+    /// Returns the color of the first visible solid fill of the node, or of its first
+    /// descendant which has one, like the rectangle of a color swatch with a label.
+    /// The opacity of the fill is applied to the alpha of the color.
+    pub fn solid_color(&self) -> Option<Color> {
+        let own_color = self
+            .fills
+            .iter()
+            .find(|paint| paint.paint_type == "SOLID" && paint.visible)
+            .and_then(|paint| {
+                paint.color.as_ref().map(|color| Color {
+                    a: color.a * paint.opacity,
+                    ..color.clone()
+                })
+            });
+        own_color.or_else(|| {
+            self.children
+                .iter()
+                .flatten()
+                .find_map(|child| child.solid_color())
+        })
+    }
+}
+
+/// Fill of the node. Only solid fills have a color.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Paint {
+    #[serde(rename = "type")]
+    pub paint_type: String,
+    #[serde(default = "default_paint_visible")]
+    pub visible: bool,
+    #[serde(default = "default_paint_opacity")]
+    pub opacity: f32,
+    pub color: Option<Color>,
+}
+
+fn default_paint_visible() -> bool {
+    true
+}
+
+fn default_paint_opacity() -> f32 {
+    1f32
+}
+
+/// RGBA color with components from 0 to 1.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+/// Bounds of the node on the canvas, in Figma units (the size of the image at scale 1).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Rectangle {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[test]
+fn test_render_bounds() {
+    let frame: Frame = serde_json::from_str(
+        r#"{
+            "id": "1:2",
+            "name": "img_card",
+            "type": "FRAME",
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 100, "height": 50 },
+            "absoluteRenderBounds": { "x": -8, "y": -4, "width": 116, "height": 66 }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(frame.render_bounds().unwrap().width, 116f32);
+
+    // Documents cached by older versions of the app have no render bounds
+    let frame: Frame = serde_json::from_str(
+        r#"{
+            "id": "1:2",
+            "name": "img_card",
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 100, "height": 50 }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(frame.render_bounds().unwrap().width, 100f32);
+}
+
+#[test]
+fn test_solid_color() {
+    let frame: Frame = serde_json::from_str(
+        r#"{
+            "id": "1:2",
+            "name": "primary",
+            "type": "COMPONENT",
+            "fills": [],
+            "children": [
+                {
+                    "id": "1:3",
+                    "name": "Label",
+                    "type": "TEXT",
+                    "fills": [{ "type": "SOLID", "visible": false, "color": { "r": 0, "g": 0, "b": 0, "a": 1 } }]
+                },
+                {
+                    "id": "1:4",
+                    "name": "Swatch",
+                    "type": "RECTANGLE",
+                    "fills": [
+                        { "type": "GRADIENT_LINEAR" },
+                        { "type": "SOLID", "opacity": 0.5, "color": { "r": 1, "g": 0.5, "b": 0, "a": 1 } }
+                    ]
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        frame.solid_color(),
+        Some(Color {
+            r: 1f32,
+            g: 0.5f32,
+            b: 0f32,
+            a: 0.5f32
+        })
+    );
+
+    // Documents cached by older versions of the app have no fills
+    let frame: Frame = serde_json::from_str(r#"{ "id": "1:2", "name": "primary" }"#).unwrap();
+    assert_eq!(frame.solid_color(), None);
 }
@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::common::error::AppError;
+use crate::common::fileutils::write_file_atomically;
+
+pub const LOCKFILE_NAME: &str = "fxa.lock";
+
+/// Lockfile `fxa.lock` which is saved next to the config and records every exported resource:
+/// ```yaml
+/// images:
+///     img_name:
+///         name: "Img Name"
+///         version: "1234567890"
+///         configHash: "..."
+///         url: "https://www.figma.com/file/..."  # only for resources exported with `--url`
+///         outputs:
+///             ./main/res/drawable-xxhdpi/img_name.webp:
+///                 nodeId: "12:34"
+///                 hash: "..."
+/// icons:
+///     ...
+/// ```
+/// Resources are stored by their res names. Maps are sorted, so the lockfile has stable diffs.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lockfile {
+    #[serde(default)]
+    pub images: BTreeMap<String, LockedResource>,
+    #[serde(default)]
+    pub icons: BTreeMap<String, LockedResource>,
+}
+
+/// Exported resource with all its scales and themes.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedResource {
+    /// Name of the resource in Figma, as it was requested for export
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Version of the Figma file the resource was exported from
+    pub version: Option<String>,
+    /// Hash of the config the resource was exported with
+    pub config_hash: String,
+    /// Link to the node if the resource is exported with `--url` instead of from the frames
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Name of the `fxa export` target if the resource is exported from its frame
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Exported files by their paths
+    pub outputs: BTreeMap<String, LockedOutput>,
+}
+
+/// Exported file of the resource.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedOutput {
+    /// Figma node the file was exported from
+    pub node_id: String,
+    /// SHA-256 of the file content
+    pub hash: String,
+}
+
+impl Lockfile {
+    /// Path to the lockfile next to the config.
+    pub fn path(yaml_config_path: &String) -> String {
+        Path::new(yaml_config_path)
+            .with_file_name(LOCKFILE_NAME)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Read the lockfile next to the config. Returns empty lockfile if there is no lockfile yet.
+    pub fn from_file(yaml_config_path: &String) -> Result<Self, AppError> {
+        let path = Lockfile::path(yaml_config_path);
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_yaml::from_str(&content)
+                .map_err(|e| AppError::LockfileRead(path, e.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Lockfile::default()),
+            Err(e) => Err(AppError::LockfileRead(path, e.to_string())),
+        }
+    }
+
+    /// Write the lockfile next to the config.
+    pub fn save(&self, yaml_config_path: &String) -> Result<(), AppError> {
+        let path = Lockfile::path(yaml_config_path);
+        let content = serde_yaml::to_string(self)
+            .map_err(|e| AppError::LockfileWrite(path.clone(), e.to_string()))?;
+        write_file_atomically(&path, content.as_bytes())
+            .map_err(|e| AppError::LockfileWrite(path, e.to_string()))
+    }
+}
+
+impl LockedResource {
+    pub fn new(name: &str, version: &Option<String>, config_hash: &str) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            version: version.clone(),
+            config_hash: config_hash.to_string(),
+            url: None,
+            target: None,
+            outputs: BTreeMap::new(),
+        }
+    }
+
+    /// Add exported file to the resource.
+    pub fn record(&mut self, path: &str, node_id: &str, content: &[u8]) {
+        self.outputs.insert(
+            path.to_string(),
+            LockedOutput {
+                node_id: node_id.to_string(),
+                hash: content_hash(content),
+            },
+        );
+    }
+
+    /// Returns `true` if the file was exported from the same version of the Figma file with
+    /// the same config, and nobody has changed it since then. Such files don't need to be exported.
+    pub fn is_up_to_date(&self, version: &Option<String>, config_hash: &str, path: &str) -> bool {
+        if version.is_none() || &self.version != version || self.config_hash != config_hash {
+            return false;
+        }
+        match (self.outputs.get(path), fs::read(path)) {
+            (Some(output), Ok(content)) => output.hash == content_hash(&content),
+            _ => false,
+        }
+    }
+}
+
+/// Record the exported resources in the locked ones.
+///
+/// Resources which failed to export keep their previous entries, with the files written now added,
+/// so the lockfile never forgets a file it owns, and such resources are exported again next time.
+/// Returns the previous files of the exported resources which are written to other paths now,
+/// e.g. after a route change, so they must be deleted.
+pub fn lock_exported(
+    locked: &mut BTreeMap<String, LockedResource>,
+    exported: BTreeMap<String, LockedResource>,
+    failed: &BTreeSet<String>,
+) -> BTreeSet<String> {
+    let mut replaced_paths: BTreeSet<String> = BTreeSet::new();
+    for (res_name, resource) in exported {
+        if !failed.contains(&res_name) {
+            if let Some(previous) = locked.insert(res_name, resource.clone()) {
+                replaced_paths.extend(
+                    previous
+                        .outputs
+                        .into_keys()
+                        .filter(|path| !resource.outputs.contains_key(path)),
+                );
+            }
+            continue;
+        }
+        match locked.get_mut(&res_name) {
+            Some(previous) => previous.outputs.extend(resource.outputs),
+            None if !resource.outputs.is_empty() => {
+                locked.insert(res_name, resource);
+            }
+            None => (),
+        }
+    }
+    replaced_paths
+}
+
+/// Returns `true` if any of the resources is exported from the frames of the config,
+/// not with `--url` or by the targets of `fxa export`.
+pub fn has_frame_resources(resources: &BTreeMap<String, LockedResource>) -> bool {
+    resources
+        .values()
+        .any(|resource| resource.url.is_none() && resource.target.is_none())
+}
+
+/// SHA-256 of the content as a hex string.
+pub fn content_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+#[test]
+fn test_is_up_to_date() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir
+        .path()
+        .join("ic_close.xml")
+        .to_string_lossy()
+        .to_string();
+    fs::write(&path, b"<vector />").unwrap();
+    let version = Some("1001".to_string());
+    let mut resource = LockedResource::new("ic_close", &version, "config");
+    resource.record(&path, "1:2", b"<vector />");
+
+    // The file is skipped
+    assert!(resource.is_up_to_date(&version, "config", &path));
+    // The file is exported again, if anything has changed
+    assert!(!resource.is_up_to_date(&Some("1002".to_string()), "config", &path));
+    assert!(!resource.is_up_to_date(&None, "config", &path));
+    assert!(!resource.is_up_to_date(&version, "other config", &path));
+    let other_path = dir.path().join("ic_open.xml").to_string_lossy().to_string();
+    fs::write(&other_path, b"<vector />").unwrap();
+    assert!(!resource.is_up_to_date(&version, "config", &other_path));
+    // The file is rewritten, if somebody has edited it
+    fs::write(&path, b"<vector android:alpha=\"0.5\" />").unwrap();
+    assert!(!resource.is_up_to_date(&version, "config", &path));
+    fs::remove_file(&path).unwrap();
+    assert!(!resource.is_up_to_date(&version, "config", &path));
+}
+
+#[test]
+fn test_reformatted_file_is_up_to_date() {
+    use crate::common::fileutils::unchanged_content;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir
+        .path()
+        .join("ic_close.xml")
+        .to_string_lossy()
+        .to_string();
+    // The file was reformatted in the IDE after the export, so it is kept on the next export
+    let reformatted = b"<vector>\n    <path fill=\"none\" d=\"M0,0\" />\n</vector>\n";
+    let exported = b"<vector><path d=\"M0,0\" fill=\"none\"/></vector>";
+    fs::write(&path, reformatted).unwrap();
+    let version = Some("1001".to_string());
+    let mut resource = LockedResource::new("ic_close", &version, "config");
+    let kept_content = unchanged_content(&path, exported).unwrap();
+    resource.record(&path, "1:2", &kept_content);
+
+    // The kept file is locked, so it is skipped without downloading next time
+    assert!(resource.is_up_to_date(&version, "config", &path));
+}
+
+#[test]
+fn test_lock_exported() {
+    let old_version = Some("1001".to_string());
+    let new_version = Some("1002".to_string());
+    let resource = |version: &Option<String>, outputs: &[(&str, &[u8])]| {
+        let mut resource = LockedResource::new("img", version, "config");
+        for (path, content) in outputs {
+            resource.record(path, "1:2", content);
+        }
+        resource
+    };
+    let mut locked = BTreeMap::from([
+        (
+            "img_card".to_string(),
+            resource(
+                &old_version,
+                &[
+                    ("hdpi/img_card.webp", b"old"),
+                    ("xhdpi/img_card.webp", b"old"),
+                ],
+            ),
+        ),
+        (
+            "img_wallet".to_string(),
+            resource(&old_version, &[("hdpi/img_wallet.webp", b"old")]),
+        ),
+        (
+            "img_moved".to_string(),
+            resource(&old_version, &[("core/hdpi/img_moved.webp", b"old")]),
+        ),
+    ]);
+    let exported = BTreeMap::from([
+        // Only one scale of the image is exported
+        (
+            "img_card".to_string(),
+            resource(&new_version, &[("hdpi/img_card.webp", b"new")]),
+        ),
+        (
+            "img_wallet".to_string(),
+            resource(&new_version, &[("hdpi/img_wallet.webp", b"new")]),
+        ),
+        ("img_promo".to_string(), resource(&new_version, &[])),
+        // The image is routed to another module now
+        (
+            "img_moved".to_string(),
+            resource(&new_version, &[("feature/hdpi/img_moved.webp", b"old")]),
+        ),
+    ]);
+    let failed = BTreeSet::from(["img_card".to_string(), "img_promo".to_string()]);
+    let replaced_paths = lock_exported(&mut locked, exported, &failed);
+
+    // Failed image keeps its version and all its files
+    let card = &locked["img_card"];
+    assert_eq!(card.version, old_version);
+    assert_eq!(
+        card.outputs["hdpi/img_card.webp"].hash,
+        content_hash(b"new")
+    );
+    assert_eq!(
+        card.outputs["xhdpi/img_card.webp"].hash,
+        content_hash(b"old")
+    );
+    assert_eq!(locked["img_wallet"].version, new_version);
+    assert!(!locked.contains_key("img_promo"));
+    assert_eq!(
+        replaced_paths.into_iter().collect::<Vec<String>>(),
+        vec!["core/hdpi/img_moved.webp"]
+    );
+}
@@ -1,3 +1,5 @@
 pub mod config;
+pub mod credentials;
 pub mod entrypoint;
 pub mod figma;
+pub mod lockfile;